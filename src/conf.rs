@@ -3,41 +3,57 @@ use super::op::*;
 
 /// Configuration parameters.
 /// Used to initialize the SX126x modem
+#[derive(Clone)]
 pub struct Config {
     /// Packet type
     pub packet_type: PacketType,
     /// LoRa sync word
     pub sync_word: u16,
+    /// Regulator mode, LDO or DC-DC
+    pub regulator_mode: RegulatorMode,
     /// Calibration parameters
     pub calib_param: CalibParam,
     /// Modulation parameters
     pub mod_params: ModParams,
     /// Power-amplifier configuration
     pub pa_config: PaConfig,
-    /// Packet parameters. Set tot none if you want to configure
-    /// these later
+    /// Packet parameters, built from either LoRaPacketParams or GfskPacketParams.
+    /// Set tot none if you want to configure these later
     pub packet_params: Option<PacketParams>,
+    /// GFSK node and broadcast addresses. Only programmed if the
+    /// GFSK packet params enable address filtering
+    pub gfsk_address: GfskAddress,
+    /// GFSK sync word. Set to None to keep the value in the modem
+    pub gfsk_sync_word: Option<GfskSyncWord>,
+    /// GFSK CRC polynomial and initial value. Set to None to keep the value in the modem
+    pub gfsk_crc: Option<GfskCrc>,
+    /// 9-bit GFSK whitening seed. Set to None to keep the value in the modem
+    pub whitening_seed: Option<u16>,
     /// TX parameters
     pub tx_params: TxParams,
+    /// Mode the modem falls back to after TX or RX
+    pub fallback_mode: FallbackMode,
+    /// Mask of the IRQs the modem raises
+    pub irq_mask: IrqMask,
     /// DIO1 IRQ mask
     pub dio1_irq_mask: IrqMask,
     /// DIO2 IRQ mask
     pub dio2_irq_mask: IrqMask,
     /// DIO3 IRW mask
     pub dio3_irq_mask: IrqMask,
-    /// RF freq, calculated using crate::calc_rf_freq
-    pub rf_freq: u32,
-    /// RF frequency in MHz
-    pub rf_frequency: u32,
+    /// RF frequency
+    pub rf_frequency: Frequency,
+    /// Frequency of the crystal oscillator or TCXO, 32 MHz on most boards
+    pub xtal_freq: Frequency,
     /// TCXO options. Set to None if not using TCXO
     pub tcxo_opts: Option<(TcxoVoltage, TcxoDelay)>,
 }
 
 impl Default for Config {
     fn default() -> Self {
-        let rf_freq = crate::calc_rf_freq(905.2, 32.0);
-        let config = Config {
+        Config {
             packet_type: PacketType::LoRa,
+            regulator_mode: RegulatorMode::Ldo,
             pa_config: PaConfig::default()
                 .set_pa_duty_cycle(0x04)
                 .set_hp_max(0x07)
@@ -51,8 +67,7 @@ impl Default for Config {
                     // IREC will tell us what to set here
                     .set_bandwidth(LoRaBandWidth::BW250)
                     .set_coding_rate(LoraCodingRate::CR4_7)
-                    .set_spread_factor(LoRaSpreadFactor::SF9)
-                    .set_low_dr_opt(false),
+                    .set_spread_factor(LoRaSpreadFactor::SF9),
             ),
             packet_params: Option::from(PacketParams::from(
                 LoRaPacketParams::default()
@@ -63,6 +78,12 @@ impl Default for Config {
                     // This flags the message as uplink (standard) or downlink (inverted)
                     .set_invert_iq(LoRaInvertIq::Standard),
             )),
+            gfsk_address: GfskAddress::default(),
+            gfsk_sync_word: None,
+            gfsk_crc: None,
+            whitening_seed: None,
+            fallback_mode: FallbackMode::StdbyRc,
+            irq_mask: IrqMask::all(),
             dio1_irq_mask: IrqMask::all(),
             dio2_irq_mask: IrqMask::none(),
             dio3_irq_mask: IrqMask::none(),
@@ -77,9 +98,8 @@ impl Default for Config {
                 true),
             sync_word: 0x12, // Private network 0x1424
             // sync_word: 0x34, // Public network 0x3444
-            rf_frequency: rf_freq,
-            rf_freq,
-        };
-        config
+            rf_frequency: Frequency::from_khz(905_200),
+            xtal_freq: Frequency::XTAL_32MHZ,
+        }
     }
 }
@@ -1,6 +1,8 @@
 #![no_std]
 
 pub mod conf;
+#[cfg(feature = "mock")]
+pub mod mock;
 pub mod op;
 pub mod reg;
 
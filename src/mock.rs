@@ -0,0 +1,894 @@
+//! A software model of the SX126x, to test code built on this crate without hardware.
+//!
+//! [`MockChip`] holds the state of the simulated modem: the chip mode, packet type,
+//! data buffer, registers and IRQ flags. It hands out an SPI device and the
+//! NRST, BUSY, ANT and DIO1 pins, which all share that state:
+//!
+//! ```
+//! # use sx126x::mock::{MockChip, MockDelay};
+//! # use sx126x::SX126x;
+//! let chip = MockChip::new();
+//! let sx = SX126x::new(chip.spi(), chip.pins(), MockDelay);
+//! ```
+//!
+//! The SPI device decodes every command and updates the state the way the modem would.
+//! Every command drives BUSY high while the modem processes it, and BUSY stays high
+//! while the modem sleeps or NRST is held low. The simulated time only advances when
+//! BUSY is read, so the operation completes as soon as the host waits for it.
+//! DIO1 is high while an IRQ that is mapped to it is set.
+//!
+//! A transmission completes as soon as SetTx is sent. A reception delivers the first
+//! packet queued with [`MockChip::queue_rx`], or times out if the queue is empty and
+//! an RX timeout was set. A GFSK packet rejected by the address filter is dropped
+//! without an IRQ and sets the address error in the packet status, so the reception
+//! only ends at the RX timeout. [`MockChip::queue_rx_header_error`] makes the next
+//! reception end with HeaderError instead.
+//! In RX, the RandomNumberGen registers change on every read.
+//!
+//! Sequences the datasheet forbids make the SPI transaction fail with a [`MockError`],
+//! for example sending a command while BUSY is high, or calibrating outside STDBY_RC.
+//! Any transaction wakes a sleeping modem, even one that only pulls NSS low.
+extern crate std;
+
+use core::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::vec::Vec;
+
+use embedded_hal::digital::{self, InputPin, OutputPin};
+use embedded_hal::spi::{self, Operation, SpiDevice};
+
+use crate::op::*;
+use crate::reg::Register;
+
+/// Bit of the GFSK RxStatus that flags a packet dropped by the address filter
+const ADDRESS_ERR: u8 = 1 << 5;
+
+/// What the modem picks up in the next reception
+enum Reception {
+    Packet(Vec<u8>),
+    /// A LoRa header with an invalid CRC
+    HeaderError,
+}
+
+/// Registers are simulated from address 0x0000 up to this address
+const REGISTER_SPACE: usize = 0x1000;
+
+/// Register reset values, from table 12-1 of the datasheet
+const REGISTER_DEFAULTS: [(Register, u8); 12] = [
+    (Register::WhiteningInitialValueMsb, 0x01),
+    (Register::WhiteningInitialValueLsb, 0x00),
+    (Register::CrcMsbInitialValue, 0x1D),
+    (Register::CrcLsbInitialValue, 0x0F),
+    (Register::CrcMsbPolynomialValue, 0x10),
+    (Register::CrcLsbPolynomialValue, 0x21),
+    (Register::LoRaSyncWordMsb, 0x14),
+    (Register::LoRaSyncWordLsb, 0x24),
+    (Register::RxGain, 0x94),
+    (Register::OcpConfiguration, 0x18),
+    (Register::XtaTrim, 0x05),
+    (Register::XtbTrim, 0x05),
+];
+
+/// Mode of the simulated modem
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// NRST is held low
+    Reset,
+    Sleep,
+    StbyRc,
+    StbyXosc,
+    Fs,
+    Rx,
+    Tx,
+}
+
+impl Mode {
+    /// Chip mode bits of the status byte
+    fn status_bits(self) -> u8 {
+        match self {
+            Mode::Reset | Mode::Sleep => 0x00,
+            Mode::StbyRc => 0x02,
+            Mode::StbyXosc => 0x03,
+            Mode::Fs => 0x04,
+            Mode::Rx => 0x05,
+            Mode::Tx => 0x06,
+        }
+    }
+
+    fn is_standby(self) -> bool {
+        matches!(self, Mode::StbyRc | Mode::StbyXosc)
+    }
+}
+
+/// Sequences the simulated modem rejects
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MockError {
+    /// A command was sent while BUSY was high
+    Busy { opcode: u8 },
+    /// A command was sent while NRST was held low
+    InReset { opcode: u8 },
+    /// The opcode is not a known command
+    UnknownOpcode(u8),
+    /// The command was sent with the wrong number of parameters
+    InvalidLength { opcode: u8, len: usize },
+    /// The command is not allowed in the current mode
+    InvalidMode { opcode: u8, mode: Mode },
+    /// The command requires SetPacketType to be sent first
+    PacketTypeNotSet { opcode: u8 },
+    /// The register address is outside the simulated register space
+    InvalidRegister(u16),
+    /// A parameter of the command has a value the modem does not support
+    InvalidParam { opcode: u8, param: u8 },
+    /// A pin was awaited for a level it will never reach,
+    /// because no operation is running that could change it
+    Deadlock,
+}
+
+impl spi::Error for MockError {
+    fn kind(&self) -> spi::ErrorKind {
+        spi::ErrorKind::Other
+    }
+}
+
+impl digital::Error for MockError {
+    fn kind(&self) -> digital::ErrorKind {
+        digital::ErrorKind::Other
+    }
+}
+
+struct State {
+    mode: Mode,
+    busy: bool,
+    warm_start: bool,
+    packet_type: Option<PacketType>,
+    fallback_mode: Mode,
+    registers: Vec<u8>,
+    buffer: [u8; 256],
+    tx_base_addr: u8,
+    rx_base_addr: u8,
+    packet_params: [u8; 9],
+    irq_mask: u16,
+    dio1_mask: u16,
+    irq_status: u16,
+    rx_payload_len: u8,
+    packet_status: [u8; 3],
+    rx_queue: VecDeque<Reception>,
+    transmitted: Vec<Vec<u8>>,
+    ant_enabled: bool,
+    nrst_high: bool,
+    device_errors: u16,
+    /// Device errors raised by every calibration, to simulate a failing one
+    calib_errors: u16,
+    /// State of the xorshift generator that stands in for the noise sampled in RX
+    noise: u32,
+}
+
+impl State {
+    fn new() -> Self {
+        let mut state = Self {
+            mode: Mode::StbyRc,
+            busy: false,
+            warm_start: false,
+            packet_type: None,
+            fallback_mode: Mode::StbyRc,
+            registers: Vec::new(),
+            buffer: [0; 256],
+            tx_base_addr: 0,
+            rx_base_addr: 0,
+            packet_params: [0; 9],
+            irq_mask: 0,
+            dio1_mask: 0,
+            irq_status: 0,
+            rx_payload_len: 0,
+            packet_status: [0; 3],
+            rx_queue: VecDeque::new(),
+            transmitted: Vec::new(),
+            ant_enabled: false,
+            nrst_high: true,
+            device_errors: 0,
+            calib_errors: 0,
+            noise: 0x2545_F491,
+        };
+        state.reset();
+        state
+    }
+
+    /// Restore the state after power on, a reset or a cold start
+    fn reset(&mut self) {
+        self.mode = Mode::StbyRc;
+        self.packet_type = None;
+        self.fallback_mode = Mode::StbyRc;
+        self.registers = std::vec![0; REGISTER_SPACE];
+        for (register, value) in REGISTER_DEFAULTS {
+            self.registers[register as usize] = value;
+        }
+        self.buffer = [0; 256];
+        self.tx_base_addr = 0;
+        self.rx_base_addr = 0;
+        self.packet_params = [0; 9];
+        self.irq_mask = 0;
+        self.dio1_mask = 0;
+        self.irq_status = 0;
+        self.rx_payload_len = 0;
+        self.device_errors = 0;
+    }
+
+    fn status(&self) -> u8 {
+        self.mode.status_bits() << 4
+    }
+
+    fn set_irq(&mut self, irq: IrqMaskBit) {
+        self.irq_status |= irq as u16 & self.irq_mask;
+    }
+
+    fn dio1(&self) -> bool {
+        self.irq_status & self.dio1_mask != 0
+    }
+
+    /// BUSY is held high while the modem sleeps or is in reset,
+    /// independent of the command being processed
+    fn busy_held(&self) -> bool {
+        matches!(self.mode, Mode::Sleep | Mode::Reset)
+    }
+
+    fn register(&self, addr: u16) -> Result<usize, MockError> {
+        let addr = addr as usize;
+        if addr >= REGISTER_SPACE {
+            return Err(MockError::InvalidRegister(addr as u16));
+        }
+        Ok(addr)
+    }
+
+    /// Update the RandomNumberGen registers, as the modem does continuously in RX
+    fn sample_noise(&mut self) {
+        self.noise ^= self.noise << 13;
+        self.noise ^= self.noise >> 17;
+        self.noise ^= self.noise << 5;
+        let start = Register::RandomNumberGen0 as usize;
+        self.registers[start..start + 4].copy_from_slice(&self.noise.to_be_bytes());
+    }
+
+    /// Whether the GFSK address filter accepts the packet, which holds
+    /// the address in its first byte
+    fn address_matches(&self, packet: &[u8]) -> bool {
+        if self.packet_type != Some(PacketType::GFSK) {
+            return true;
+        }
+        let node = self.registers[Register::NodeAddress as usize];
+        let broadcast = self.registers[Register::BroadcastAddress as usize];
+        match (self.packet_params[4], packet.first()) {
+            (0x00, _) => true,
+            (0x01, Some(&address)) => address == node,
+            (0x02, Some(&address)) => address == node || address == broadcast,
+            _ => false,
+        }
+    }
+
+    /// Payload length field of the current packet parameters
+    fn payload_len(&self) -> u8 {
+        match self.packet_type {
+            Some(PacketType::GFSK) => self.packet_params[6],
+            _ => self.packet_params[3],
+        }
+    }
+
+    /// Execute a single SPI transaction. mosi holds the bytes sent by the host,
+    /// the returned vector the bytes clocked out by the modem
+    fn transfer(&mut self, mosi: &[u8]) -> Result<Vec<u8>, MockError> {
+        if self.mode == Mode::Sleep {
+            // The falling edge of NSS wakes the modem, the command itself is lost
+            if !self.warm_start {
+                self.reset();
+            }
+            self.mode = Mode::StbyRc;
+            self.busy = true;
+            return Ok(std::vec![0; mosi.len()]);
+        }
+        let Some(&opcode) = mosi.first() else {
+            return Ok(Vec::new());
+        };
+        if !self.nrst_high {
+            return Err(MockError::InReset { opcode });
+        }
+        if self.busy {
+            return Err(MockError::Busy { opcode });
+        }
+
+        let mut miso = std::vec![self.status(); mosi.len()];
+        let params = &mosi[1..];
+        let expect_len = |len: usize| {
+            if params.len() == len {
+                Ok(())
+            } else {
+                Err(MockError::InvalidLength {
+                    opcode,
+                    len: params.len(),
+                })
+            }
+        };
+        let mode = self.mode;
+        let expect_mode = |allowed: bool| {
+            if allowed {
+                Ok(())
+            } else {
+                Err(MockError::InvalidMode { opcode, mode })
+            }
+        };
+
+        match opcode {
+            // SetSleep
+            0x84 => {
+                expect_len(1)?;
+                expect_mode(self.mode.is_standby())?;
+                self.warm_start = params[0] & 0x04 != 0;
+                self.mode = Mode::Sleep;
+            }
+            // SetStandby
+            0x80 => {
+                expect_len(1)?;
+                self.mode = match params[0] {
+                    0 => Mode::StbyRc,
+                    _ => Mode::StbyXosc,
+                };
+            }
+            // SetFs
+            0xC1 => {
+                expect_len(1)?;
+                expect_mode(self.mode.is_standby() || self.mode == Mode::Fs)?;
+                self.mode = Mode::Fs;
+            }
+            // SetTx
+            0x83 => {
+                expect_len(3)?;
+                expect_mode(self.mode.is_standby() || self.mode == Mode::Fs)?;
+                self.packet_type
+                    .ok_or(MockError::PacketTypeNotSet { opcode })?;
+                let start = self.tx_base_addr as usize;
+                let packet = (0..self.payload_len() as usize)
+                    .map(|i| self.buffer[(start + i) % 256])
+                    .collect();
+                self.transmitted.push(packet);
+                self.set_irq(IrqMaskBit::TxDone);
+                self.mode = self.fallback_mode;
+            }
+            // SetRx
+            0x82 => {
+                expect_len(3)?;
+                expect_mode(self.mode.is_standby() || self.mode == Mode::Fs)?;
+                self.packet_type
+                    .ok_or(MockError::PacketTypeNotSet { opcode })?;
+                let timeout = u32::from_be_bytes([0, params[0], params[1], params[2]]);
+                let received = match self.rx_queue.pop_front() {
+                    Some(Reception::Packet(packet)) if self.address_matches(&packet) => {
+                        self.packet_status[0] &= !ADDRESS_ERR;
+                        let start = self.rx_base_addr as usize;
+                        for (i, byte) in packet.iter().enumerate() {
+                            self.buffer[(start + i) % 256] = *byte;
+                        }
+                        self.rx_payload_len = packet.len() as u8;
+                        self.set_irq(IrqMaskBit::RxDone);
+                        true
+                    }
+                    Some(Reception::HeaderError) => {
+                        self.set_irq(IrqMaskBit::HeaderError);
+                        true
+                    }
+                    // Dropped by the address filter without an IRQ, the modem keeps listening
+                    Some(Reception::Packet(_)) => {
+                        self.packet_status[0] |= ADDRESS_ERR;
+                        false
+                    }
+                    None => false,
+                };
+                if received {
+                    self.mode = self.fallback_mode;
+                } else if timeout != 0 && timeout != 0xFF_FFFF {
+                    self.set_irq(IrqMaskBit::Timeout);
+                    self.mode = self.fallback_mode;
+                } else {
+                    self.mode = Mode::Rx;
+                }
+            }
+            // StopTimerOnPreamble, SetLoRaSymbNumTimeout
+            0x9F | 0xA0 => expect_len(1)?,
+            // SetRxDutyCycle
+            0x94 => {
+                expect_len(6)?;
+                expect_mode(self.mode.is_standby())?;
+                self.mode = Mode::Rx;
+            }
+            // SetCad
+            0xC5 => {
+                expect_len(1)?;
+                expect_mode(self.mode.is_standby() || self.mode == Mode::Fs)?;
+                self.set_irq(IrqMaskBit::CadDone);
+                self.mode = self.fallback_mode;
+            }
+            // SetTxContinuousWave, SetTxInfinitePreamble
+            0xD1 | 0xD2 => {
+                expect_len(1)?;
+                expect_mode(self.mode.is_standby() || self.mode == Mode::Fs)?;
+                self.mode = Mode::Tx;
+            }
+            // SetRegulatorMode, SetDIO2AsRfSwitchCtrl
+            0x96 | 0x9D => expect_len(1)?,
+            // Calibrate, CalibrateImage
+            0x89 | 0x98 => {
+                expect_len(if opcode == 0x89 { 1 } else { 2 })?;
+                expect_mode(self.mode == Mode::StbyRc)?;
+                self.device_errors |= self.calib_errors;
+            }
+            // SetPaConfig
+            0x95 => expect_len(4)?,
+            // SetRxTxFallbackMode
+            0x93 => {
+                expect_len(1)?;
+                self.fallback_mode = match params[0] {
+                    0x40 => Mode::Fs,
+                    0x30 => Mode::StbyXosc,
+                    _ => Mode::StbyRc,
+                };
+            }
+            // WriteRegister
+            0x0D => {
+                if params.len() < 3 {
+                    return Err(MockError::InvalidLength {
+                        opcode,
+                        len: params.len(),
+                    });
+                }
+                let addr = u16::from_be_bytes([params[0], params[1]]);
+                for (i, byte) in params[2..].iter().enumerate() {
+                    let register = self.register(addr.wrapping_add(i as u16))?;
+                    self.registers[register] = *byte;
+                }
+            }
+            // ReadRegister
+            0x1D => {
+                if params.len() < 4 {
+                    return Err(MockError::InvalidLength {
+                        opcode,
+                        len: params.len(),
+                    });
+                }
+                let addr = u16::from_be_bytes([params[0], params[1]]);
+                if self.mode == Mode::Rx {
+                    self.sample_noise();
+                }
+                for i in 0..params.len() - 3 {
+                    let register = self.register(addr.wrapping_add(i as u16))?;
+                    miso[4 + i] = self.registers[register];
+                }
+            }
+            // WriteBuffer
+            0x0E => {
+                if params.len() < 2 {
+                    return Err(MockError::InvalidLength {
+                        opcode,
+                        len: params.len(),
+                    });
+                }
+                let offset = params[0] as usize;
+                for (i, byte) in params[1..].iter().enumerate() {
+                    self.buffer[(offset + i) % 256] = *byte;
+                }
+            }
+            // ReadBuffer
+            0x1E => {
+                if params.len() < 3 {
+                    return Err(MockError::InvalidLength {
+                        opcode,
+                        len: params.len(),
+                    });
+                }
+                let offset = params[0] as usize;
+                for i in 0..params.len() - 2 {
+                    miso[3 + i] = self.buffer[(offset + i) % 256];
+                }
+            }
+            // SetDioIrqParams
+            0x08 => {
+                expect_len(8)?;
+                self.irq_mask = u16::from_be_bytes([params[0], params[1]]);
+                self.dio1_mask = u16::from_be_bytes([params[2], params[3]]);
+            }
+            // GetIrqStatus
+            0x12 => {
+                expect_len(3)?;
+                miso[2..4].copy_from_slice(&self.irq_status.to_be_bytes());
+            }
+            // ClearIrqStatus
+            0x02 => {
+                expect_len(2)?;
+                self.irq_status &= !u16::from_be_bytes([params[0], params[1]]);
+            }
+            // SetDIO3AsTCXOCtrl
+            0x97 => {
+                expect_len(4)?;
+                expect_mode(self.mode == Mode::StbyRc)?;
+            }
+            // SetRfFrequency
+            0x86 => {
+                expect_len(4)?;
+                self.packet_type
+                    .ok_or(MockError::PacketTypeNotSet { opcode })?;
+            }
+            // SetPacketType
+            0x8A => {
+                expect_len(1)?;
+                expect_mode(self.mode.is_standby())?;
+                let packet_type = params[0].try_into().map_err(|_| MockError::InvalidParam {
+                    opcode,
+                    param: params[0],
+                })?;
+                self.packet_type = Some(packet_type);
+            }
+            // GetPacketType
+            0x11 => {
+                expect_len(2)?;
+                miso[2] = self.packet_type.map(|p| p as u8).unwrap_or(0);
+            }
+            // SetTxParams, SetModulationParams
+            0x8E | 0x8B => {
+                expect_len(if opcode == 0x8E { 2 } else { 8 })?;
+                self.packet_type
+                    .ok_or(MockError::PacketTypeNotSet { opcode })?;
+            }
+            // SetPacketParams
+            0x8C => {
+                expect_len(9)?;
+                self.packet_type
+                    .ok_or(MockError::PacketTypeNotSet { opcode })?;
+                self.packet_params.copy_from_slice(params);
+            }
+            // SetCadParams
+            0x88 => expect_len(7)?,
+            // SetBufferBaseAddress
+            0x8F => {
+                expect_len(2)?;
+                self.tx_base_addr = params[0];
+                self.rx_base_addr = params[1];
+            }
+            // GetStatus
+            0xC0 => expect_len(1)?,
+            // GetRxBufferStatus
+            0x13 => {
+                expect_len(3)?;
+                miso[2] = self.rx_payload_len;
+                miso[3] = self.rx_base_addr;
+            }
+            // GetPacketStatus
+            0x14 => {
+                expect_len(4)?;
+                miso[2..].copy_from_slice(&self.packet_status);
+            }
+            // GetRssiInst
+            0x15 => expect_len(2)?,
+            // GetStats
+            0x10 => {
+                expect_len(7)?;
+                miso[2..].fill(0);
+            }
+            // ResetStats
+            0x00 => expect_len(6)?,
+            // GetDeviceErrors
+            0x17 => {
+                expect_len(3)?;
+                miso[2..].copy_from_slice(&self.device_errors.to_be_bytes());
+            }
+            // ClearDeviceErrors
+            0x07 => {
+                expect_len(2)?;
+                self.device_errors = 0;
+            }
+            _ => return Err(MockError::UnknownOpcode(opcode)),
+        }
+        // BUSY stays high while the modem processes the command
+        self.busy = true;
+        Ok(miso)
+    }
+}
+
+/// Handle to a simulated SX126x. Clones share the same modem
+#[derive(Clone)]
+pub struct MockChip {
+    state: Rc<RefCell<State>>,
+}
+
+impl Default for MockChip {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockChip {
+    /// A modem that just powered on, in STDBY_RC
+    pub fn new() -> Self {
+        Self {
+            state: Rc::new(RefCell::new(State::new())),
+        }
+    }
+
+    /// The SPI device connected to this modem
+    pub fn spi(&self) -> MockSpi {
+        MockSpi(self.clone())
+    }
+
+    /// The NRST, BUSY, ANT and DIO1 pins, in the order SX126x::new takes them
+    pub fn pins(&self) -> (MockPin, MockPin, MockPin, MockPin) {
+        (
+            MockPin::new(self, PinKind::Nrst),
+            MockPin::new(self, PinKind::Busy),
+            MockPin::new(self, PinKind::Ant),
+            MockPin::new(self, PinKind::Dio1),
+        )
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.state.borrow().mode
+    }
+
+    pub fn packet_type(&self) -> Option<PacketType> {
+        self.state.borrow().packet_type
+    }
+
+    /// Current value of a register
+    pub fn register(&self, register: Register) -> u8 {
+        self.state.borrow().registers[register as usize]
+    }
+
+    /// Contents of the data buffer
+    pub fn buffer(&self) -> [u8; 256] {
+        self.state.borrow().buffer
+    }
+
+    /// IRQ flags that are currently set
+    pub fn irq_status(&self) -> IrqStatus {
+        self.state.borrow().irq_status.into()
+    }
+
+    /// Level of BUSY, without advancing the simulated time
+    pub fn busy(&self) -> bool {
+        let state = self.state.borrow();
+        state.busy || state.busy_held()
+    }
+
+    pub fn dio1(&self) -> bool {
+        self.state.borrow().dio1()
+    }
+
+    pub fn ant_enabled(&self) -> bool {
+        self.state.borrow().ant_enabled
+    }
+
+    /// Payloads sent with SetTx, oldest first
+    pub fn transmitted(&self) -> Vec<Vec<u8>> {
+        self.state.borrow().transmitted.clone()
+    }
+
+    /// Queue a packet that is received by the next SetRx
+    pub fn queue_rx(&self, payload: &[u8]) {
+        debug_assert!(payload.len() <= 255);
+        self.state
+            .borrow_mut()
+            .rx_queue
+            .push_back(Reception::Packet(payload.to_vec()));
+    }
+
+    /// Queue a LoRa header error, which ends the next SetRx with HeaderError
+    pub fn queue_rx_header_error(&self) {
+        self.state
+            .borrow_mut()
+            .rx_queue
+            .push_back(Reception::HeaderError);
+    }
+
+    /// Set the three bytes GetPacketStatus returns
+    pub fn set_packet_status(&self, packet_status: [u8; 3]) {
+        self.state.borrow_mut().packet_status = packet_status;
+    }
+
+    /// Device errors the modem raises on every Calibrate and CalibrateImage,
+    /// as if the calibration failed.
+    /// They are reported by GetDeviceErrors until cleared with ClearDeviceErrors
+    pub fn set_calib_errors(&self, errors: u16) {
+        self.state.borrow_mut().calib_errors = errors;
+    }
+
+    /// Set IRQ flags, as if the modem raised them. Flags that are
+    /// disabled with SetDioIrqParams are ignored, like on the modem
+    pub fn raise_irq(&self, irq: IrqMaskBit) {
+        self.state.borrow_mut().set_irq(irq);
+    }
+}
+
+/// SPI device of a simulated SX126x. Implements both the blocking and
+/// the async SpiDevice, every transaction is executed immediately
+pub struct MockSpi(MockChip);
+
+impl MockSpi {
+    fn run(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), MockError> {
+        // The modem is full duplex: collect what the host clocks out
+        let mut mosi = Vec::new();
+        for op in operations.iter() {
+            match op {
+                Operation::Read(buf) => mosi.extend(core::iter::repeat_n(0, buf.len())),
+                Operation::Write(buf) => mosi.extend_from_slice(buf),
+                Operation::Transfer(read, write) => {
+                    let len = read.len().max(write.len());
+                    mosi.extend_from_slice(write);
+                    mosi.extend(core::iter::repeat_n(0, len - write.len()));
+                }
+                Operation::TransferInPlace(buf) => mosi.extend_from_slice(buf),
+                Operation::DelayNs(_) => {}
+            }
+        }
+
+        let miso = self.0.state.borrow_mut().transfer(&mosi)?;
+
+        // And hand out what the modem clocked in
+        let mut miso = miso.into_iter();
+        for op in operations.iter_mut() {
+            match op {
+                Operation::Read(buf) | Operation::TransferInPlace(buf) => {
+                    buf.iter_mut().for_each(|b| *b = miso.next().unwrap())
+                }
+                Operation::Write(buf) => {
+                    miso.by_ref().take(buf.len()).for_each(drop);
+                }
+                Operation::Transfer(read, write) => {
+                    let len = read.len().max(write.len());
+                    for (i, byte) in miso.by_ref().take(len).enumerate() {
+                        if let Some(b) = read.get_mut(i) {
+                            *b = byte;
+                        }
+                    }
+                }
+                Operation::DelayNs(_) => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+impl spi::ErrorType for MockSpi {
+    type Error = MockError;
+}
+
+impl SpiDevice for MockSpi {
+    fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), MockError> {
+        self.run(operations)
+    }
+}
+
+impl embedded_hal_async::spi::SpiDevice for MockSpi {
+    async fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), MockError> {
+        self.run(operations)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum PinKind {
+    Nrst,
+    Busy,
+    Ant,
+    Dio1,
+}
+
+/// One of the pins of a simulated SX126x. NRST and ANT are outputs of the host,
+/// BUSY and DIO1 inputs. Reading BUSY completes the operation that drove it high.
+pub struct MockPin {
+    chip: MockChip,
+    kind: PinKind,
+}
+
+impl MockPin {
+    fn new(chip: &MockChip, kind: PinKind) -> Self {
+        Self {
+            chip: chip.clone(),
+            kind,
+        }
+    }
+
+    /// Level of an input pin, advancing the simulated time if it is BUSY
+    fn level(&mut self) -> bool {
+        let mut state = self.chip.state.borrow_mut();
+        match self.kind {
+            PinKind::Busy => core::mem::take(&mut state.busy) || state.busy_held(),
+            PinKind::Dio1 => state.dio1(),
+            PinKind::Nrst => state.nrst_high,
+            PinKind::Ant => state.ant_enabled,
+        }
+    }
+
+    fn wait_for(&mut self, high: bool) -> Result<(), MockError> {
+        if self.kind == PinKind::Busy && !high {
+            let mut state = self.chip.state.borrow_mut();
+            if state.busy_held() {
+                return Err(MockError::Deadlock);
+            }
+            state.busy = false;
+            return Ok(());
+        }
+        if self.level() == high {
+            Ok(())
+        } else {
+            Err(MockError::Deadlock)
+        }
+    }
+}
+
+impl digital::ErrorType for MockPin {
+    type Error = MockError;
+}
+
+impl InputPin for MockPin {
+    fn is_high(&mut self) -> Result<bool, MockError> {
+        Ok(self.level())
+    }
+
+    fn is_low(&mut self) -> Result<bool, MockError> {
+        Ok(!self.level())
+    }
+}
+
+impl OutputPin for MockPin {
+    fn set_low(&mut self) -> Result<(), MockError> {
+        let mut state = self.chip.state.borrow_mut();
+        match self.kind {
+            PinKind::Nrst => {
+                state.nrst_high = false;
+                state.reset();
+                state.mode = Mode::Reset;
+            }
+            PinKind::Ant => state.ant_enabled = false,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), MockError> {
+        let mut state = self.chip.state.borrow_mut();
+        match self.kind {
+            PinKind::Nrst if !state.nrst_high => {
+                state.nrst_high = true;
+                state.mode = Mode::StbyRc;
+                // The modem starts up after a reset
+                state.busy = true;
+            }
+            PinKind::Ant => state.ant_enabled = true,
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl embedded_hal_async::digital::Wait for MockPin {
+    async fn wait_for_high(&mut self) -> Result<(), MockError> {
+        self.wait_for(true)
+    }
+
+    async fn wait_for_low(&mut self) -> Result<(), MockError> {
+        self.wait_for(false)
+    }
+
+    async fn wait_for_rising_edge(&mut self) -> Result<(), MockError> {
+        self.wait_for(true)
+    }
+
+    async fn wait_for_falling_edge(&mut self) -> Result<(), MockError> {
+        self.wait_for(false)
+    }
+
+    async fn wait_for_any_edge(&mut self) -> Result<(), MockError> {
+        Err(MockError::Deadlock)
+    }
+}
+
+/// A delay that returns immediately, for the blocking API
+pub struct MockDelay;
+
+impl embedded_hal::delay::DelayNs for MockDelay {
+    fn delay_ns(&mut self, _ns: u32) {}
+}
@@ -1,3 +1,5 @@
+use super::ConfigError;
+
 /// Channel Activity Detection (CAD) commands
 ///
 /// | Command      | Opcode | Parameters                                      | Description                                                                      |
@@ -53,11 +55,11 @@ impl CadDetPeak {
     pub const MIN: u8 = 18;
     pub const MAX: u8 = 35;
 
-    pub const fn new(value: u8) -> Result<Self, &'static str> {
+    pub const fn new(value: u8) -> Result<Self, ConfigError> {
         if value >= Self::MIN && value <= Self::MAX {
             Ok(CadDetPeak(value))
         } else {
-            Err("Value out of range")
+            Err(ConfigError::CadDetPeakOutOfRange(value))
         }
     }
 }
@@ -77,16 +79,16 @@ impl CadDetMin {
     pub const MIN: u8 = 10;
     pub const MAX: u8 = 10;
 
-    pub const fn new(value: u8) -> Result<Self, &'static str> {
+    pub const fn new(value: u8) -> Result<Self, ConfigError> {
         if value >= Self::MIN && value <= Self::MAX {
             Ok(CadDetMin(value))
         } else {
-            Err("Value out of range")
+            Err(ConfigError::CadDetMinOutOfRange(value))
         }
     }
 
     /// Not recommended to use this function but here for utility
-    pub const fn new_override(value: u8) -> Result<Self, &'static str> {
+    pub const fn new_override(value: u8) -> Result<Self, ConfigError> {
         Ok(CadDetMin(value))
     }
 }
@@ -119,9 +121,9 @@ impl From<CadExit> for u8 {
 
 /// cadTimeout: Timeout for the CAD operation in units of 15.625 us
 /// 
-/// The timeout is a 16-bit value, so the maximum value is 0xFFFF
+/// The timeout is a 24-bit value, so the maximum value is 0xFFFFFF
 /// 
-/// It is auto converted to two u8 values for the command
+/// It is auto converted to three u8 values for the command
 #[derive(Copy, Clone, Debug)]
 #[allow(non_camel_case_types)]
 pub struct CadTimeout(u32);
@@ -130,11 +132,11 @@ impl CadTimeout {
     pub const MIN: u32 = 0;
     pub const MAX: u32 = 0xFFFFFF; // 24-bit maximum value
 
-    pub const fn new(value: u32) -> Result<Self, &'static str> {
-        if value >= Self::MIN && value <= Self::MAX {
+    pub const fn new(value: u32) -> Result<Self, ConfigError> {
+        if matches!(value, Self::MIN..=Self::MAX) {
             Ok(CadTimeout(value))
         } else {
-            Err("Value out of range")
+            Err(ConfigError::CadTimeoutOutOfRange(value))
         }
     }
 
@@ -1,3 +1,5 @@
+use super::Frequency;
+
 #[derive(Copy, Clone)]
 pub struct CalibParam {
     inner: u8,
@@ -63,8 +65,7 @@ impl CalibParam {
             | (adc_pulse_en as u8) << 3
             | (adc_bulk_n_en as u8) << 4
             | (adc_bulk_p_en as u8) << 5
-            | (image_en as u8) << 6
-            | (0 as u8) << 7;
+            | (image_en as u8) << 6;
         Self { inner }
     }
 
@@ -73,31 +74,69 @@ impl CalibParam {
     }
 }
 
-#[derive(Copy, Clone)]
-#[repr(u16)]
-pub enum CalibImageFreq {
-    MHz430_440 = 0x6B_6F,
-    MHz470_510 = 0x75_81,
-    MHz779_787 = 0xC1_C5,
-    MHz863_870 = 0xD7_DB,
-    MHz902_928 = 0xE1_E9,
+/// Frequency band of the image calibration, as sent with CalibrateImage.
+/// Both bounds are given in steps of 4 MHz, see section 9.2.1
+///
+/// | Frequency band [MHz] | freq1 | freq2 |
+/// |----------------------|-------|-------|
+/// | 430 - 440            | 0x6B  | 0x6F  |
+/// | 470 - 510            | 0x75  | 0x81  |
+/// | 779 - 787            | 0xC1  | 0xC5  |
+/// | 863 - 870            | 0xD7  | 0xDB  |
+/// | 902 - 928            | 0xE1  | 0xE9  |
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CalibImageFreq {
+    freq1: u8,
+    freq2: u8,
 }
 
 impl From<CalibImageFreq> for [u8; 2] {
     fn from(val: CalibImageFreq) -> Self {
-        (val as u16).to_be_bytes()
+        [val.freq1, val.freq2]
     }
 }
 
+/// Width of a single step of freq1 and freq2
+const CALIB_IMAGE_STEP_HZ: u32 = 4_000_000;
+
+#[allow(non_upper_case_globals)]
 impl CalibImageFreq {
-    pub fn from_rf_frequency(rf_frequency: u32) -> Self {
-        match rf_frequency / 1000000 {
+    pub const MHz430_440: Self = Self::new(0x6B, 0x6F);
+    pub const MHz470_510: Self = Self::new(0x75, 0x81);
+    pub const MHz779_787: Self = Self::new(0xC1, 0xC5);
+    pub const MHz863_870: Self = Self::new(0xD7, 0xDB);
+    pub const MHz902_928: Self = Self::new(0xE1, 0xE9);
+
+    /// Band from freq1 * 4 MHz to freq2 * 4 MHz
+    pub const fn new(freq1: u8, freq2: u8) -> Self {
+        Self { freq1, freq2 }
+    }
+
+    /// The smallest band of 4 MHz steps that covers min to max. The upper bound
+    /// is limited to 1020 MHz
+    pub fn from_range(min: Frequency, max: Frequency) -> Self {
+        let freq1 = (min.as_hz() / CALIB_IMAGE_STEP_HZ).min(u8::MAX as u32 - 1) as u8;
+        let freq2 = max.as_hz().div_ceil(CALIB_IMAGE_STEP_HZ).min(u8::MAX as u32) as u8;
+        Self::new(freq1, freq2.max(freq1 + 1))
+    }
+
+    /// The band from the datasheet table containing rf_frequency, or the
+    /// 4 MHz steps around it for frequencies outside those bands
+    pub fn from_rf_frequency(rf_frequency: Frequency) -> Self {
+        match rf_frequency.as_hz() / 1_000_000 {
             902..=928 => Self::MHz902_928,
             863..=870 => Self::MHz863_870,
             779..=787 => Self::MHz779_787,
             470..=510 => Self::MHz470_510,
             430..=440 => Self::MHz430_440,
-            _ => Self::MHz902_928, // Default
+            _ => Self::from_range(rf_frequency, rf_frequency),
         }
     }
+
+    /// Whether rf_frequency lies within the calibrated band
+    pub fn contains(&self, rf_frequency: Frequency) -> bool {
+        let hz = rf_frequency.as_hz() as u64;
+        let step = CALIB_IMAGE_STEP_HZ as u64;
+        self.freq1 as u64 * step <= hz && hz <= self.freq2 as u64 * step
+    }
 }
@@ -67,4 +67,114 @@ impl DeviceErrors {
     pub fn pa_ramp_err(self) -> bool {
         (self.inner & 1 << 8) > 0
     }
+
+    /// True if any error is reported
+    pub fn any(self) -> bool {
+        self.inner != 0
+    }
+}
+
+/// Parameters the modem does not accept. Reported before anything is sent to the modem
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ConfigError {
+    /// cadDetPeak outside CadDetPeak::MIN to CadDetPeak::MAX
+    CadDetPeakOutOfRange(u8),
+    /// cadDetMin outside CadDetMin::MIN to CadDetMin::MAX
+    CadDetMinOutOfRange(u8),
+    /// cadTimeout does not fit in 24 bits
+    CadTimeoutOutOfRange(u32),
+    /// The GFSK sync word, of the contained length, is not 1 to 8 bytes long
+    GfskSyncWordLength(usize),
+    /// The whitening seed does not fit in 9 bits
+    WhiteningSeedOutOfRange(u16),
+    /// An RX window in symbols requires LoRa modulation parameters,
+    /// but the current ones are GFSK
+    RxWindowRequiresLoRa,
+    /// An RX window of the contained number of symbols does not fit in the 24-bit RX timeout
+    RxWindowTooLong(u16),
+    /// An RX window of 0 symbols, which the modem would take as a single RX without timeout
+    RxWindowEmpty,
+    /// The LoRa symbol number timeout exceeds LORA_SYMB_NUM_TIMEOUT_MAX
+    SymbNumTimeoutOutOfRange(u8),
+    /// Low data rate optimization is forced to a value that contradicts the symbol time,
+    /// without LoraModParams::override_low_dr_opt
+    LowDataRateOptConflict,
+}
+
+impl core::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::CadDetPeakOutOfRange(value) => write!(
+                f,
+                "cadDetPeak {} is outside {} to {}",
+                value,
+                super::CadDetPeak::MIN,
+                super::CadDetPeak::MAX
+            ),
+            Self::CadDetMinOutOfRange(value) => write!(
+                f,
+                "cadDetMin {} is outside {} to {}",
+                value,
+                super::CadDetMin::MIN,
+                super::CadDetMin::MAX
+            ),
+            Self::CadTimeoutOutOfRange(value) => {
+                write!(f, "cadTimeout {:#x} does not fit in 24 bits", value)
+            }
+            Self::GfskSyncWordLength(len) => {
+                write!(f, "GFSK sync word of {} bytes, must be 1 to 8 bytes long", len)
+            }
+            Self::WhiteningSeedOutOfRange(seed) => {
+                write!(f, "whitening seed {:#x} does not fit in 9 bits", seed)
+            }
+            Self::RxWindowRequiresLoRa => {
+                write!(f, "RX window in symbols requires LoRa modulation parameters")
+            }
+            Self::RxWindowEmpty => write!(f, "RX window of 0 symbols"),
+            Self::RxWindowTooLong(symbols) => write!(
+                f,
+                "RX window of {} symbols does not fit in the 24-bit RX timeout",
+                symbols
+            ),
+            Self::SymbNumTimeoutOutOfRange(symb_num) => write!(
+                f,
+                "LoRa symbol number timeout {} exceeds {}",
+                symb_num,
+                super::LORA_SYMB_NUM_TIMEOUT_MAX
+            ),
+            Self::LowDataRateOptConflict => write!(
+                f,
+                "low data rate optimization must be enabled if and only if the symbol time exceeds 16.38 ms"
+            ),
+        }
+    }
 }
+
+impl core::error::Error for ConfigError {}
+
+/// A value read from the modem that does not decode into the expected type,
+/// for example because of a glitch on the SPI bus
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    /// Not a known packet type
+    PacketType(u8),
+    /// Not a LoRa spreading factor
+    SpreadFactor(u8),
+    /// Not a LoRa bandwidth
+    Bandwidth(u8),
+    /// Not a LoRa coding rate
+    CodingRate(u8),
+}
+
+impl core::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::PacketType(value) => write!(f, "invalid packet type {:#04x}", value),
+            Self::SpreadFactor(value) => write!(f, "invalid LoRa spreading factor {:#04x}", value),
+            Self::Bandwidth(value) => write!(f, "invalid LoRa bandwidth {:#04x}", value),
+            Self::CodingRate(value) => write!(f, "invalid LoRa coding rate {:#04x}", value),
+        }
+    }
+}
+
+impl core::error::Error for DecodeError {}
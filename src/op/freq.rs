@@ -0,0 +1,46 @@
+/// A frequency in Hz, used for the RF frequency and the crystal frequency.
+///
+/// The modem sets the RF frequency in PLL steps of Fxtal / 2^25, about 0.95 Hz
+/// with a 32 MHz crystal (section 13.4.1):
+///
+/// RFfrequency = RFfreq * Fxtal / 2^25
+///
+/// Conversions between Hz and PLL steps use 64-bit integer math and round
+/// to the nearest value, so they are exact to within half a step
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Frequency {
+    hz: u32,
+}
+
+impl Frequency {
+    /// Frequency of the crystal oscillator fitted on most SX126x boards
+    pub const XTAL_32MHZ: Self = Self::from_mhz(32);
+
+    pub const fn from_hz(hz: u32) -> Self {
+        Self { hz }
+    }
+
+    pub const fn from_khz(khz: u32) -> Self {
+        Self::from_hz(khz * 1_000)
+    }
+
+    pub const fn from_mhz(mhz: u32) -> Self {
+        Self::from_hz(mhz * 1_000_000)
+    }
+
+    pub const fn as_hz(self) -> u32 {
+        self.hz
+    }
+
+    /// The RFfreq value sent with SetRfFrequency, for a crystal of frequency xtal
+    pub const fn to_pll_steps(self, xtal: Frequency) -> u32 {
+        let xtal = xtal.hz as u64;
+        ((((self.hz as u64) << 25) + xtal / 2) / xtal) as u32
+    }
+
+    /// The frequency corresponding to an RFfreq value, for a crystal of frequency xtal
+    pub const fn from_pll_steps(steps: u32, xtal: Frequency) -> Self {
+        let hz = (steps as u64 * xtal.hz as u64 + (1 << 24)) >> 25;
+        Self::from_hz(hz as u32)
+    }
+}
@@ -4,3 +4,37 @@ pub enum StandbyConfig {
     StbyRc = 0x00,
     StbyXOSC = 0x01,
 }
+
+/// Sleep configuration, as sent with SetSleep
+///
+/// | Bit | Meaning                                                    |
+/// |-----|------------------------------------------------------------|
+/// | 2   | 0: cold start, 1: warm start (configuration is retained)   |
+/// | 0   | 0: RTC timeout disabled, 1: wake-up on RTC timeout          |
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SleepConfig {
+    /// Retain the configuration while sleeping. On a cold start the
+    /// whole configuration is lost and has to be written again
+    pub warm_start: bool,
+    /// Wake up automatically when the RTC times out
+    pub rtc_wakeup: bool,
+}
+
+impl From<SleepConfig> for u8 {
+    fn from(val: SleepConfig) -> Self {
+        (val.warm_start as u8) << 2 | (val.rtc_wakeup as u8)
+    }
+}
+
+/// Regulator used by the modem. By default only the LDO is used.
+/// The DC-DC converter reduces the current consumption, but requires
+/// the inductor to be fitted on the board
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum RegulatorMode {
+    /// Only LDO used for all modes
+    #[default]
+    Ldo = 0x00,
+    /// DC-DC + LDO used for STBY_XOSC, FS, RX and TX modes
+    DcDc = 0x01,
+}
@@ -68,6 +68,14 @@ impl From<u16> for IrqStatus {
     }
 }
 
+impl From<IrqStatus> for IrqMask {
+    fn from(status: IrqStatus) -> Self {
+        Self {
+            inner: status.inner,
+        }
+    }
+}
+
 impl core::fmt::Debug for IrqStatus {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(
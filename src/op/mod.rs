@@ -3,6 +3,7 @@ pub mod cad;
 pub mod op_modes;
 pub mod calib;
 pub mod err;
+pub mod freq;
 pub mod init;
 pub mod irq;
 pub mod modulation;
@@ -15,6 +16,7 @@ pub use cad::*;
 pub use op_modes::*;
 pub use calib::*;
 pub use err::*;
+pub use freq::*;
 pub use init::*;
 pub use irq::*;
 pub use modulation::*;
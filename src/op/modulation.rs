@@ -1,39 +1,89 @@
-pub struct ModParams {
-    inner: [u8; 8],
-    // pub lora: LoraModParams,
+use super::{Frequency, PacketType};
+
+/// Modulation parameters, as sent with SetModulationParams.
+/// Build these from either [`LoraModParams`] or [`GfskModParams`].
+#[derive(Copy, Clone)]
+pub enum ModParams {
+    LoRa(LoraModParams),
+    Gfsk(GfskModParams),
 }
 
-impl From<ModParams> for [u8; 8] {
-    fn from(val: ModParams) -> Self {
-        val.inner
+impl ModParams {
+    /// The 8 parameter bytes of SetModulationParams. The GFSK bit rate and
+    /// frequency deviation are derived from the crystal frequency xtal
+    pub fn to_bytes(&self, xtal: Frequency) -> [u8; 8] {
+        match self {
+            ModParams::LoRa(lora) => lora.into(),
+            ModParams::Gfsk(gfsk) => gfsk.to_bytes(xtal),
+        }
     }
-}
 
-impl From<&ModParams> for [u8; 8] {
-    fn from(val: &ModParams) -> Self {
-        val.inner
+    /// The packet type these modulation parameters belong to
+    pub fn packet_type(&self) -> PacketType {
+        match self {
+            ModParams::LoRa(_) => PacketType::LoRa,
+            ModParams::Gfsk(_) => PacketType::GFSK,
+        }
     }
-}
 
-impl ModParams {
-    pub fn get_spread_factor(&self) -> LoRaSpreadFactor {
-        self.inner[0].into()
+    /// The LoRa parameters, or `None` for a GFSK configuration
+    pub fn lora(&self) -> Option<&LoraModParams> {
+        match self {
+            ModParams::LoRa(lora) => Some(lora),
+            ModParams::Gfsk(_) => None,
+        }
+    }
+
+    /// The GFSK parameters, or `None` for a LoRa configuration
+    pub fn gfsk(&self) -> Option<&GfskModParams> {
+        match self {
+            ModParams::LoRa(_) => None,
+            ModParams::Gfsk(gfsk) => Some(gfsk),
+        }
+    }
+
+    pub fn get_spread_factor(&self) -> Option<LoRaSpreadFactor> {
+        self.lora().map(LoraModParams::spread_factor)
     }
-    pub fn get_bandwidth(&self) -> LoRaBandWidth {
-        self.inner[1].into()
+    pub fn get_bandwidth(&self) -> Option<LoRaBandWidth> {
+        self.lora().map(LoraModParams::bandwidth)
     }
-    pub fn get_coding_rate(&self) -> LoraCodingRate {
-        self.inner[2].into()
+    pub fn get_coding_rate(&self) -> Option<LoraCodingRate> {
+        self.lora().map(LoraModParams::coding_rate)
     }
-    pub fn get_low_dr_opt(&self) -> bool {
-        self.inner[3] != 0
+    pub fn get_low_dr_opt(&self) -> Option<bool> {
+        self.lora().map(LoraModParams::low_dr_opt)
+    }
+    pub fn get_symbol_time_ns(&self) -> Option<u64> {
+        self.lora().map(LoraModParams::symbol_time_ns)
+    }
+
+    pub fn get_bitrate(&self) -> Option<u32> {
+        self.gfsk().map(GfskModParams::bitrate)
+    }
+    pub fn get_pulse_shape(&self) -> Option<GfskPulseShape> {
+        self.gfsk().map(GfskModParams::pulse_shape)
+    }
+    pub fn get_rx_bandwidth(&self) -> Option<GfskBandwidth> {
+        self.gfsk().map(GfskModParams::bandwidth)
+    }
+    pub fn get_freq_deviation(&self) -> Option<u32> {
+        self.gfsk().map(GfskModParams::freq_deviation)
     }
 }
 
+impl Default for ModParams {
+    fn default() -> Self {
+        LoraModParams::default().into()
+    }
+}
+
+pub use gfsk::*;
 pub use lora::*;
 
 mod lora {
     use super::ModParams;
+    use crate::op::DecodeError;
     #[derive(Copy, Clone)]
     #[repr(u8)]
     pub enum LoRaSpreadFactor {
@@ -47,18 +97,20 @@ mod lora {
         SF12 = 0x0C,
     }
 
-    impl From<u8> for LoRaSpreadFactor {
-        fn from(value: u8) -> Self {
+    impl TryFrom<u8> for LoRaSpreadFactor {
+        type Error = DecodeError;
+
+        fn try_from(value: u8) -> Result<Self, DecodeError> {
             match value {
-                0x05 => Self::SF5,
-                0x06 => Self::SF6,
-                0x07 => Self::SF7,
-                0x08 => Self::SF8,
-                0x09 => Self::SF9,
-                0x0A => Self::SF10,
-                0x0B => Self::SF11,
-                0x0C => Self::SF12,
-                _ => panic!("Invalid LoRa spread factor"),
+                0x05 => Ok(Self::SF5),
+                0x06 => Ok(Self::SF6),
+                0x07 => Ok(Self::SF7),
+                0x08 => Ok(Self::SF8),
+                0x09 => Ok(Self::SF9),
+                0x0A => Ok(Self::SF10),
+                0x0B => Ok(Self::SF11),
+                0x0C => Ok(Self::SF12),
+                _ => Err(DecodeError::SpreadFactor(value)),
             }
         }
     }
@@ -88,20 +140,22 @@ mod lora {
         BW500 = 0x06,
     }
 
-    impl From<u8> for LoRaBandWidth {
-        fn from(value: u8) -> Self {
+    impl TryFrom<u8> for LoRaBandWidth {
+        type Error = DecodeError;
+
+        fn try_from(value: u8) -> Result<Self, DecodeError> {
             match value {
-                0x00 => Self::BW7,
-                0x08 => Self::BW10,
-                0x01 => Self::BW15,
-                0x09 => Self::BW20,
-                0x02 => Self::BW31,
-                0x0A => Self::BW41,
-                0x03 => Self::BW62,
-                0x04 => Self::BW125,
-                0x05 => Self::BW250,
-                0x06 => Self::BW500,
-                _ => panic!("Invalid LoRa bandwidth"),
+                0x00 => Ok(Self::BW7),
+                0x08 => Ok(Self::BW10),
+                0x01 => Ok(Self::BW15),
+                0x09 => Ok(Self::BW20),
+                0x02 => Ok(Self::BW31),
+                0x0A => Ok(Self::BW41),
+                0x03 => Ok(Self::BW62),
+                0x04 => Ok(Self::BW125),
+                0x05 => Ok(Self::BW250),
+                0x06 => Ok(Self::BW500),
+                _ => Err(DecodeError::Bandwidth(value)),
             }
         }
     }
@@ -121,6 +175,23 @@ mod lora {
                 LoRaBandWidth::BW500 => 500.0,
             }
         }
+
+        /// The bandwidth as a fraction (numerator, denominator) of 125 kHz,
+        /// which allows exact integer symbol time calculations
+        const fn ratio_125k(&self) -> (u64, u64) {
+            match self {
+                LoRaBandWidth::BW7 => (1, 16),
+                LoRaBandWidth::BW10 => (1, 12),
+                LoRaBandWidth::BW15 => (1, 8),
+                LoRaBandWidth::BW20 => (1, 6),
+                LoRaBandWidth::BW31 => (1, 4),
+                LoRaBandWidth::BW41 => (1, 3),
+                LoRaBandWidth::BW62 => (1, 2),
+                LoRaBandWidth::BW125 => (1, 1),
+                LoRaBandWidth::BW250 => (2, 1),
+                LoRaBandWidth::BW500 => (4, 1),
+            }
+        }
     }
 
     #[derive(Copy, Clone)]
@@ -132,23 +203,32 @@ mod lora {
         CR4_8 = 0x04,
     }
 
-    impl From<u8> for LoraCodingRate {
-        fn from(value: u8) -> Self {
+    impl TryFrom<u8> for LoraCodingRate {
+        type Error = DecodeError;
+
+        fn try_from(value: u8) -> Result<Self, DecodeError> {
             match value {
-                0x01 => Self::CR4_5,
-                0x02 => Self::CR4_6,
-                0x03 => Self::CR4_7,
-                0x04 => Self::CR4_8,
-                _ => panic!("Invalid LoRa coding rate"),
+                0x01 => Ok(Self::CR4_5),
+                0x02 => Ok(Self::CR4_6),
+                0x03 => Ok(Self::CR4_7),
+                0x04 => Ok(Self::CR4_8),
+                _ => Err(DecodeError::CodingRate(value)),
             }
         }
     }
 
+    /// Symbol time above which low data rate optimization is recommended: 16.38 ms
+    pub const LOW_DR_OPT_SYMBOL_TIME_NS: u64 = 16_380_000;
+
+    #[derive(Copy, Clone)]
     pub struct LoraModParams {
         spread_factor: LoRaSpreadFactor,
         pub(crate) bandwidth: LoRaBandWidth,
         coding_rate: LoraCodingRate,
-        low_data_rate_optimize: bool,
+        /// Forced low data rate optimization, or None to derive it from the symbol time
+        low_data_rate_optimize: Option<bool>,
+        /// Send the forced value even if it contradicts the symbol time
+        low_dr_opt_override: bool,
     }
 
     impl Default for LoraModParams {
@@ -157,7 +237,8 @@ mod lora {
                 spread_factor: LoRaSpreadFactor::SF7,
                 bandwidth: LoRaBandWidth::BW125,
                 coding_rate: LoraCodingRate::CR4_5,
-                low_data_rate_optimize: false,
+                low_data_rate_optimize: None,
+                low_dr_opt_override: false,
             }
         }
     }
@@ -176,33 +257,299 @@ mod lora {
             self
         }
 
+        /// Force low data rate optimization on or off. Setting a value that contradicts
+        /// LoraModParams::low_dr_opt_recommended makes SX126x::set_mod_params fail
+        /// with ConfigError::LowDataRateOptConflict, use override_low_dr_opt to send it anyway
         pub fn set_low_dr_opt(mut self, low_dr_opt: bool) -> Self {
-            self.low_data_rate_optimize = low_dr_opt;
+            self.low_data_rate_optimize = Some(low_dr_opt);
+            self.low_dr_opt_override = false;
+            self
+        }
+
+        /// Force low data rate optimization on or off, even if the value contradicts
+        /// LoraModParams::low_dr_opt_recommended. The value is sent to the modem as is
+        pub fn override_low_dr_opt(mut self, low_dr_opt: bool) -> Self {
+            self.low_data_rate_optimize = Some(low_dr_opt);
+            self.low_dr_opt_override = true;
+            self
+        }
+
+        /// Enable low data rate optimization whenever the symbol time exceeds 16.38 ms,
+        /// as recommended by the datasheet. This is the default
+        pub fn set_low_dr_opt_auto(mut self) -> Self {
+            self.low_data_rate_optimize = None;
+            self.low_dr_opt_override = false;
             self
         }
+
+        pub fn spread_factor(&self) -> LoRaSpreadFactor {
+            self.spread_factor
+        }
+        pub fn bandwidth(&self) -> LoRaBandWidth {
+            self.bandwidth
+        }
+        pub fn coding_rate(&self) -> LoraCodingRate {
+            self.coding_rate
+        }
+        /// Low data rate optimization as sent to the modem: the forced value,
+        /// or the recommended one in auto mode
+        pub fn low_dr_opt(&self) -> bool {
+            self.low_data_rate_optimize
+                .unwrap_or_else(|| self.low_dr_opt_recommended())
+        }
+
+        /// Whether the symbol time exceeds 16.38 ms, above which the datasheet
+        /// recommends low data rate optimization
+        pub fn low_dr_opt_recommended(&self) -> bool {
+            self.symbol_time_ns() > LOW_DR_OPT_SYMBOL_TIME_NS
+        }
+
+        /// Whether low data rate optimization is forced to a value that
+        /// contradicts the recommendation
+        pub fn low_dr_opt_conflict(&self) -> bool {
+            self.low_data_rate_optimize
+                .is_some_and(|low_dr_opt| low_dr_opt != self.low_dr_opt_recommended())
+        }
+
+        /// Whether the driver refuses these parameters: the forced low data rate
+        /// optimization contradicts the recommendation and was not overridden
+        pub(crate) fn low_dr_opt_rejected(&self) -> bool {
+            self.low_dr_opt_conflict() && !self.low_dr_opt_override
+        }
+
+        /// Duration of a single symbol in ns: Tsym = 2^SF / BW
+        pub fn symbol_time_ns(&self) -> u64 {
+            let (num, den) = self.bandwidth.ratio_125k();
+            // 1 s / 125 kHz = 8000 ns
+            (1u64 << self.spread_factor as u8) * 8000 * den / num
+        }
+    }
+
+    impl From<&LoraModParams> for [u8; 8] {
+        fn from(val: &LoraModParams) -> Self {
+            [
+                val.spread_factor as u8,
+                val.bandwidth as u8,
+                val.coding_rate as u8,
+                val.low_dr_opt() as u8,
+                0x00,
+                0x00,
+                0x00,
+                0x00,
+            ]
+        }
     }
 
+    /// Low data rate optimization is enabled in the resulting modulation parameters when
+    /// the symbol time exceeds 16.38 ms, unless it was forced with LoraModParams::set_low_dr_opt
     impl From<LoraModParams> for ModParams {
         fn from(val: LoraModParams) -> Self {
-            ModParams {
-                inner: [
-                    val.spread_factor as u8,
-                    val.bandwidth as u8,
-                    val.coding_rate as u8,
-                    val.low_data_rate_optimize as u8,
-                    0x00,
-                    0x00,
-                    0x00,
-                    0x00,
-                ],
-                // lora: val,
+            ModParams::LoRa(val)
+        }
+    }
+}
+
+mod gfsk {
+    use super::{Frequency, ModParams};
+
+    /// Gaussian filter applied to the GFSK modulation
+    ///
+    /// | PulseShape             | Value |
+    /// |------------------------|-------|
+    /// | No filter applied      | 0x00  |
+    /// | Gaussian BT 0.3        | 0x08  |
+    /// | Gaussian BT 0.5        | 0x09  |
+    /// | Gaussian BT 0.7        | 0x0A  |
+    /// | Gaussian BT 1          | 0x0B  |
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    #[repr(u8)]
+    pub enum GfskPulseShape {
+        /// No filter applied
+        NoFilter = 0x00,
+        /// Gaussian BT 0.3
+        BT0_3 = 0x08,
+        /// Gaussian BT 0.5
+        BT0_5 = 0x09,
+        /// Gaussian BT 0.7
+        BT0_7 = 0x0A,
+        /// Gaussian BT 1
+        BT1_0 = 0x0B,
+    }
+
+    /// GFSK receiver bandwidth (double side band)
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    #[repr(u8)]
+    pub enum GfskBandwidth {
+        /// 4.8 kHz
+        BW4800 = 0x1F,
+        /// 5.8 kHz
+        BW5800 = 0x17,
+        /// 7.3 kHz
+        BW7300 = 0x0F,
+        /// 9.7 kHz
+        BW9700 = 0x1E,
+        /// 11.7 kHz
+        BW11700 = 0x16,
+        /// 14.6 kHz
+        BW14600 = 0x0E,
+        /// 19.5 kHz
+        BW19500 = 0x1D,
+        /// 23.4 kHz
+        BW23400 = 0x15,
+        /// 29.3 kHz
+        BW29300 = 0x0D,
+        /// 39.0 kHz
+        BW39000 = 0x1C,
+        /// 46.9 kHz
+        BW46900 = 0x14,
+        /// 58.6 kHz
+        BW58600 = 0x0C,
+        /// 78.2 kHz
+        BW78200 = 0x1B,
+        /// 93.8 kHz
+        BW93800 = 0x13,
+        /// 117.3 kHz
+        BW117300 = 0x0B,
+        /// 156.2 kHz
+        BW156200 = 0x1A,
+        /// 187.2 kHz
+        BW187200 = 0x12,
+        /// 234.3 kHz
+        BW234300 = 0x0A,
+        /// 312.0 kHz
+        BW312000 = 0x19,
+        /// 373.6 kHz
+        BW373600 = 0x11,
+        /// 467.0 kHz
+        BW467000 = 0x09,
+    }
+
+    impl GfskBandwidth {
+        pub fn to_hz(&self) -> u32 {
+            match self {
+                GfskBandwidth::BW4800 => 4_800,
+                GfskBandwidth::BW5800 => 5_800,
+                GfskBandwidth::BW7300 => 7_300,
+                GfskBandwidth::BW9700 => 9_700,
+                GfskBandwidth::BW11700 => 11_700,
+                GfskBandwidth::BW14600 => 14_600,
+                GfskBandwidth::BW19500 => 19_500,
+                GfskBandwidth::BW23400 => 23_400,
+                GfskBandwidth::BW29300 => 29_300,
+                GfskBandwidth::BW39000 => 39_000,
+                GfskBandwidth::BW46900 => 46_900,
+                GfskBandwidth::BW58600 => 58_600,
+                GfskBandwidth::BW78200 => 78_200,
+                GfskBandwidth::BW93800 => 93_800,
+                GfskBandwidth::BW117300 => 117_300,
+                GfskBandwidth::BW156200 => 156_200,
+                GfskBandwidth::BW187200 => 187_200,
+                GfskBandwidth::BW234300 => 234_300,
+                GfskBandwidth::BW312000 => 312_000,
+                GfskBandwidth::BW373600 => 373_600,
+                GfskBandwidth::BW467000 => 467_000,
             }
         }
     }
 
-    impl Default for ModParams {
+    /// GFSK modulation parameters
+    ///
+    /// | Byte | 1-3       | 4          | 5         | 6-8         |
+    /// |------|-----------|------------|-----------|-------------|
+    /// | GFSK | br[23:0]  | PulseShape | Bandwidth | Fdev[23:0]  |
+    ///
+    /// br = 32 * Fxtal / bit rate, Fdev = (frequency deviation * 2^25) / Fxtal
+    #[derive(Copy, Clone)]
+    pub struct GfskModParams {
+        bitrate: u32,
+        pulse_shape: GfskPulseShape,
+        bandwidth: GfskBandwidth,
+        freq_deviation: u32,
+    }
+
+    impl Default for GfskModParams {
         fn default() -> Self {
-            LoraModParams::default().into()
+            Self {
+                bitrate: 50_000,
+                pulse_shape: GfskPulseShape::BT0_5,
+                bandwidth: GfskBandwidth::BW117300,
+                freq_deviation: 25_000,
+            }
+        }
+    }
+
+    impl GfskModParams {
+        /// Bit rate in bits per second, valid from 600 to 300 000 b/s
+        pub fn set_bitrate(mut self, bitrate: u32) -> Self {
+            debug_assert!(bitrate >= 600);
+            debug_assert!(bitrate <= 300_000);
+            self.bitrate = bitrate;
+            self
+        }
+
+        pub fn set_pulse_shape(mut self, pulse_shape: GfskPulseShape) -> Self {
+            self.pulse_shape = pulse_shape;
+            self
+        }
+
+        /// Receiver bandwidth. Should be at least bit rate + 2 * frequency deviation
+        pub fn set_bandwidth(mut self, bandwidth: GfskBandwidth) -> Self {
+            self.bandwidth = bandwidth;
+            self
+        }
+
+        /// Frequency deviation in Hz
+        pub fn set_freq_deviation(mut self, freq_deviation: u32) -> Self {
+            debug_assert!(freq_deviation <= 200_000);
+            self.freq_deviation = freq_deviation;
+            self
+        }
+
+        pub fn bitrate(&self) -> u32 {
+            self.bitrate
+        }
+        pub fn pulse_shape(&self) -> GfskPulseShape {
+            self.pulse_shape
+        }
+        pub fn bandwidth(&self) -> GfskBandwidth {
+            self.bandwidth
+        }
+        pub fn freq_deviation(&self) -> u32 {
+            self.freq_deviation
+        }
+
+        /// The 24-bit bit rate register value, br = 32 * Fxtal / bit rate
+        pub fn bitrate_reg(&self, xtal: Frequency) -> u32 {
+            let bitrate = self.bitrate.max(1) as u64;
+            ((32 * xtal.as_hz() as u64 + bitrate / 2) / bitrate) as u32 & 0x00FF_FFFF
+        }
+
+        /// The 24-bit frequency deviation register value, Fdev = deviation * 2^25 / Fxtal
+        pub fn freq_deviation_reg(&self, xtal: Frequency) -> u32 {
+            let xtal = xtal.as_hz() as u64;
+            ((((self.freq_deviation as u64) << 25) + xtal / 2) / xtal) as u32 & 0x00FF_FFFF
+        }
+
+        /// The 8 parameter bytes of SetModulationParams for a crystal of frequency xtal
+        pub fn to_bytes(&self, xtal: Frequency) -> [u8; 8] {
+            let br = self.bitrate_reg(xtal).to_be_bytes();
+            let fdev = self.freq_deviation_reg(xtal).to_be_bytes();
+            [
+                br[1],
+                br[2],
+                br[3],
+                self.pulse_shape as u8,
+                self.bandwidth as u8,
+                fdev[1],
+                fdev[2],
+                fdev[3],
+            ]
+        }
+    }
+
+    impl From<GfskModParams> for ModParams {
+        fn from(val: GfskModParams) -> Self {
+            ModParams::Gfsk(val)
         }
     }
 }
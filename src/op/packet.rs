@@ -1,3 +1,5 @@
+use super::DecodeError;
+
 #[repr(u8)]
 #[derive(Copy, Clone, PartialEq)]
 pub enum PacketType {
@@ -14,32 +16,68 @@ impl core::fmt::Debug for PacketType {
     }
 }
 
-impl From<u8> for PacketType {
-    fn from(b: u8) -> Self {
+impl TryFrom<u8> for PacketType {
+    type Error = DecodeError;
+
+    fn try_from(b: u8) -> Result<Self, DecodeError> {
         match b {
-            0x00 => PacketType::GFSK,
-            0x01 => PacketType::LoRa,
-            _ => unreachable!(),
+            0x00 => Ok(PacketType::GFSK),
+            0x01 => Ok(PacketType::LoRa),
+            _ => Err(DecodeError::PacketType(b)),
         }
     }
 }
 
-pub struct PacketParams {
-    inner: [u8; 9],
+/// Packet parameters, as sent with SetPacketParams.
+/// Build these from either [`LoRaPacketParams`] or [`GfskPacketParams`].
+#[derive(Copy, Clone)]
+pub enum PacketParams {
+    LoRa(LoRaPacketParams),
+    Gfsk(GfskPacketParams),
 }
 
 impl From<PacketParams> for [u8; 9] {
     fn from(val: PacketParams) -> Self {
-        val.inner
+        (&val).into()
     }
 }
 
 impl From<&PacketParams> for [u8; 9] {
     fn from(val: &PacketParams) -> Self {
-        val.inner
+        match val {
+            PacketParams::LoRa(lora) => lora.into(),
+            PacketParams::Gfsk(gfsk) => gfsk.into(),
+        }
+    }
+}
+
+impl PacketParams {
+    /// The packet type these packet parameters belong to
+    pub fn packet_type(&self) -> PacketType {
+        match self {
+            PacketParams::LoRa(_) => PacketType::LoRa,
+            PacketParams::Gfsk(_) => PacketType::GFSK,
+        }
+    }
+
+    /// The LoRa parameters, or `None` for a GFSK configuration
+    pub fn lora(&self) -> Option<&LoRaPacketParams> {
+        match self {
+            PacketParams::LoRa(lora) => Some(lora),
+            PacketParams::Gfsk(_) => None,
+        }
+    }
+
+    /// The GFSK parameters, or `None` for a LoRa configuration
+    pub fn gfsk(&self) -> Option<&GfskPacketParams> {
+        match self {
+            PacketParams::LoRa(_) => None,
+            PacketParams::Gfsk(gfsk) => Some(gfsk),
+        }
     }
 }
 
+pub use gfsk::*;
 pub use lora::*;
 
 mod lora {
@@ -64,20 +102,8 @@ mod lora {
     }
 
     /// Only used in FSK mode
-    #[repr(u8)]
-    #[derive(Copy, Clone)]
-    pub enum LoRaCrcTypeConfig {
-        /// No CRC
-        CrcOff = 0x01,
-        /// CRC computed on 1 byte
-        Crc1Byte = 0x00,
-        /// CRC computed on 2 bytes
-        Crc2Bytes = 0x02,
-        /// CRC computed on 1 byte, inverted
-        Crc1ByteInv = 0x04,
-        /// CRC computed on 2 bytes, inverted
-        Crc2BytesInv = 0x06,
-    }
+    #[deprecated(note = "Use GfskCrcType instead")]
+    pub type LoRaCrcTypeConfig = super::GfskCrcType;
 
     #[repr(u8)]
     #[derive(Copy, Clone)]
@@ -88,6 +114,7 @@ mod lora {
         Inverted = 0x01,
     }
 
+    #[derive(Copy, Clone)]
     pub struct LoRaPacketParams {
         /// preamble length: number of symbols sent as preamble
         /// The preamble length is a 16-bit value which represents
@@ -107,43 +134,33 @@ mod lora {
         pub invert_iq: LoRaInvertIq,
     }
 
-    impl From<LoRaPacketParams> for PacketParams {
-        fn from(val: LoRaPacketParams) -> Self {
+    impl From<&LoRaPacketParams> for [u8; 9] {
+        fn from(val: &LoRaPacketParams) -> Self {
             let preamble_len = val.preamble_len.to_be_bytes();
 
-            PacketParams {
-                inner: [
-                    preamble_len[0],
-                    preamble_len[1],
-                    val.header_type as u8,
-                    val.payload_len,
-                    val.crc_type as u8,
-                    val.invert_iq as u8,
-                    0x00,
-                    0x00,
-                    0x00,
-                ],
-            }
+            [
+                preamble_len[0],
+                preamble_len[1],
+                val.header_type as u8,
+                val.payload_len,
+                val.crc_type as u8,
+                val.invert_iq as u8,
+                0x00,
+                0x00,
+                0x00,
+            ]
+        }
+    }
+
+    impl From<LoRaPacketParams> for PacketParams {
+        fn from(val: LoRaPacketParams) -> Self {
+            PacketParams::LoRa(val)
         }
     }
 
     impl From<&LoRaPacketParams> for PacketParams {
         fn from(val: &LoRaPacketParams) -> Self {
-            let preamble_len = val.preamble_len.to_be_bytes();
-
-            PacketParams {
-                inner: [
-                    preamble_len[0],
-                    preamble_len[1],
-                    val.header_type as u8,
-                    val.payload_len,
-                    val.crc_type as u8,
-                    val.invert_iq as u8,
-                    0x00,
-                    0x00,
-                    0x00,
-                ],
-            }
+            PacketParams::LoRa(*val)
         }
     }
 
@@ -188,6 +205,243 @@ mod lora {
     }
 }
 
+mod gfsk {
+    use super::PacketParams;
+
+    /// Length of the preamble the receiver needs to detect before
+    /// it starts looking for the sync word
+    #[repr(u8)]
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    pub enum GfskPreambleDetector {
+        /// Preamble detector off
+        Off = 0x00,
+        /// 8 bits
+        Bits8 = 0x04,
+        /// 16 bits
+        Bits16 = 0x05,
+        /// 24 bits
+        Bits24 = 0x06,
+        /// 32 bits
+        Bits32 = 0x07,
+    }
+
+    /// Address filtering performed on the first payload byte.
+    /// The addresses are programmed in the NodeAddress and BroadcastAddress registers
+    #[repr(u8)]
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    pub enum GfskAddrComp {
+        /// Address filtering disabled
+        Off = 0x00,
+        /// Filtering on node address
+        Node = 0x01,
+        /// Filtering on node and broadcast addresses
+        NodeAndBroadcast = 0x02,
+    }
+
+    /// Addresses compared against the first payload byte when
+    /// address filtering is enabled through GfskAddrComp
+    #[derive(Copy, Clone, Debug, Default, PartialEq)]
+    pub struct GfskAddress {
+        /// Node address, used with GfskAddrComp::Node and GfskAddrComp::NodeAndBroadcast
+        pub node: u8,
+        /// Broadcast address, used with GfskAddrComp::NodeAndBroadcast
+        pub broadcast: u8,
+    }
+
+    #[repr(u8)]
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    pub enum GfskPacketLength {
+        /// The packet length is known on both sides, no header is added
+        Fixed = 0x00,
+        /// The packet is of variable size, the length byte is added to the packet
+        Variable = 0x01,
+    }
+
+    #[repr(u8)]
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    pub enum GfskCrcType {
+        /// No CRC
+        CrcOff = 0x01,
+        /// CRC computed on 1 byte
+        Crc1Byte = 0x00,
+        /// CRC computed on 2 bytes
+        Crc2Bytes = 0x02,
+        /// CRC computed on 1 byte, inverted
+        Crc1ByteInv = 0x04,
+        /// CRC computed on 2 bytes, inverted
+        Crc2BytesInv = 0x06,
+    }
+
+    /// Polynomial and initial value of the GFSK CRC, written to the
+    /// CrcPolynomialValue and CrcInitialValue registers
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    pub struct GfskCrc {
+        pub polynomial: u16,
+        pub initial_value: u16,
+    }
+
+    impl GfskCrc {
+        /// IBM CRC, use together with GfskCrcType::Crc2Bytes
+        pub const IBM: Self = Self::new(0x8005, 0xFFFF);
+        /// CCITT CRC, use together with GfskCrcType::Crc2BytesInv
+        pub const CCITT: Self = Self::new(0x1021, 0x1D0F);
+
+        pub const fn new(polynomial: u16, initial_value: u16) -> Self {
+            Self {
+                polynomial,
+                initial_value,
+            }
+        }
+    }
+
+    /// GFSK sync word of 1 to 8 bytes, written to the SyncWord0 register onwards
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    pub struct GfskSyncWord {
+        bytes: [u8; 8],
+        len: u8,
+    }
+
+    impl GfskSyncWord {
+        /// None if sync_word is not 1 to 8 bytes long
+        pub fn new(sync_word: &[u8]) -> Option<Self> {
+            if sync_word.is_empty() || sync_word.len() > 8 {
+                return None;
+            }
+            let mut bytes = [0; 8];
+            bytes[..sync_word.len()].copy_from_slice(sync_word);
+            Some(Self {
+                bytes,
+                len: sync_word.len() as u8,
+            })
+        }
+
+        pub fn as_bytes(&self) -> &[u8] {
+            &self.bytes[..self.len as usize]
+        }
+    }
+
+    #[repr(u8)]
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    pub enum GfskWhitening {
+        /// No encoding
+        Off = 0x00,
+        /// Whitening enabled
+        On = 0x01,
+    }
+
+    #[derive(Copy, Clone)]
+    pub struct GfskPacketParams {
+        /// Number of preamble bits sent by the transmitter
+        pub preamble_len: u16, // 1, 2
+        /// Preamble length the receiver needs to detect
+        pub preamble_detector: GfskPreambleDetector, // 3
+        /// Sync word length in bits, from 0 to 64
+        pub sync_word_len: u8, // 4
+        /// Address filtering mode
+        pub addr_comp: GfskAddrComp, // 5
+        /// Fixed or variable length packets
+        pub packet_length: GfskPacketLength, // 6
+        /// Size of the payload (in bytes) to transmit or maximum size of the
+        /// payload that the receiver can accept.
+        pub payload_len: u8, // 7
+        /// CRC type
+        pub crc_type: GfskCrcType, // 8
+        /// Whitening
+        pub whitening: GfskWhitening, // 9
+    }
+
+    impl From<&GfskPacketParams> for [u8; 9] {
+        fn from(val: &GfskPacketParams) -> Self {
+            let preamble_len = val.preamble_len.to_be_bytes();
+
+            [
+                preamble_len[0],
+                preamble_len[1],
+                val.preamble_detector as u8,
+                val.sync_word_len,
+                val.addr_comp as u8,
+                val.packet_length as u8,
+                val.payload_len,
+                val.crc_type as u8,
+                val.whitening as u8,
+            ]
+        }
+    }
+
+    impl From<GfskPacketParams> for PacketParams {
+        fn from(val: GfskPacketParams) -> Self {
+            PacketParams::Gfsk(val)
+        }
+    }
+
+    impl From<&GfskPacketParams> for PacketParams {
+        fn from(val: &GfskPacketParams) -> Self {
+            PacketParams::Gfsk(*val)
+        }
+    }
+
+    impl Default for GfskPacketParams {
+        fn default() -> Self {
+            Self {
+                preamble_len: 0x0020,
+                preamble_detector: GfskPreambleDetector::Bits16,
+                sync_word_len: 16,
+                addr_comp: GfskAddrComp::Off,
+                packet_length: GfskPacketLength::Variable,
+                payload_len: 0x00,
+                crc_type: GfskCrcType::CrcOff,
+                whitening: GfskWhitening::Off,
+            }
+        }
+    }
+
+    impl GfskPacketParams {
+        /// Preamble length in bits, valid from 0x0008 to 0xFFFF
+        pub fn set_preamble_len(mut self, preamble_len: u16) -> Self {
+            debug_assert!(preamble_len >= 8);
+            self.preamble_len = preamble_len;
+            self
+        }
+
+        pub fn set_preamble_detector(mut self, preamble_detector: GfskPreambleDetector) -> Self {
+            self.preamble_detector = preamble_detector;
+            self
+        }
+
+        /// Sync word length in bits, valid from 0x00 to 0x40
+        pub fn set_sync_word_len(mut self, sync_word_len: u8) -> Self {
+            debug_assert!(sync_word_len <= 64);
+            self.sync_word_len = sync_word_len;
+            self
+        }
+
+        pub fn set_addr_comp(mut self, addr_comp: GfskAddrComp) -> Self {
+            self.addr_comp = addr_comp;
+            self
+        }
+
+        pub fn set_packet_length(mut self, packet_length: GfskPacketLength) -> Self {
+            self.packet_length = packet_length;
+            self
+        }
+
+        pub fn set_payload_len(mut self, payload_len: u8) -> Self {
+            self.payload_len = payload_len;
+            self
+        }
+
+        pub fn set_crc_type(mut self, crc_type: GfskCrcType) -> Self {
+            self.crc_type = crc_type;
+            self
+        }
+
+        pub fn set_whitening(mut self, whitening: GfskWhitening) -> Self {
+            self.whitening = whitening;
+            self
+        }
+    }
+}
+
 impl Default for PacketParams {
     fn default() -> Self {
         LoRaPacketParams::default().into()
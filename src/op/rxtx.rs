@@ -1,3 +1,8 @@
+use super::{IrqStatus, LoRaCrcType, LoRaHeaderType, LoRaPacketParams, ModParams, Status};
+
+/// Duration of a single RTC step: 15.625 μs
+pub(crate) const RTC_STEP_NS: u64 = 15_625;
+
 #[derive(Copy, Clone)]
 pub struct RxTxTimeout {
     inner: [u8; 3],
@@ -17,22 +22,188 @@ impl RxTxTimeout {
         Self { inner }
     }
 
+    /// A timeout spanning the given number of LoRa symbols, rounded up to the
+    /// next RTC step. Returns None for GFSK parameters, for 0 symbols, which would
+    /// disable the timeout, or if the timeout does not fit in 24 bits
+    pub fn from_lora_symbols(mod_params: &ModParams, symbols: u16) -> Option<Self> {
+        if symbols == 0 {
+            return None;
+        }
+        let time_ns = symbols as u64 * mod_params.get_symbol_time_ns()?;
+        let steps = time_ns.div_ceil(RTC_STEP_NS);
+        if steps >= 0xFF_FFFF {
+            return None;
+        }
+        Some((steps as u32).into())
+    }
+
     pub const fn continuous_rx() -> Self {
         Self {
             inner: [0xFF, 0xFF, 0xFF],
         }
     }
+
+    /// The timeout in μs, rounded up. Returns None if the timeout is disabled,
+    /// which is the case for 0 and for continuous RX (0xFFFFFF)
+    pub const fn to_us(&self) -> Option<u32> {
+        let steps = u32::from_be_bytes([0, self.inner[0], self.inner[1], self.inner[2]]);
+        if steps == 0 || steps == 0xFF_FFFF {
+            return None;
+        }
+        Some((steps as u64 * RTC_STEP_NS).div_ceil(1000) as u32)
+    }
 }
 
 impl From<u32> for RxTxTimeout {
     fn from(val: u32) -> Self {
         let bytes = val.to_be_bytes();
         Self {
-            inner: [bytes[0], bytes[1], bytes[2]],
+            inner: [bytes[1], bytes[2], bytes[3]],
+        }
+    }
+}
+
+/// Largest number of symbols a LoRaSymbNumTimeout can express
+pub const LORA_SYMB_NUM_TIMEOUT_MAX: u8 = 248;
+
+/// Number of symbols the modem waits for to validate a LoRa reception, in the
+/// mantissa and exponent encoding of Semtech's reference driver. The modem waits
+/// for mantissa * 2 * 4^exponent symbols, with a mantissa of at most 31
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct LoRaSymbNumTimeout {
+    mantissa: u8,
+    exponent: u8,
+}
+
+impl LoRaSymbNumTimeout {
+    /// The timeout closest to, and at least, symb_num symbols.
+    /// Returns None above LORA_SYMB_NUM_TIMEOUT_MAX symbols
+    pub const fn new(symb_num: u8) -> Option<Self> {
+        if symb_num > LORA_SYMB_NUM_TIMEOUT_MAX {
+            return None;
+        }
+        let mut mantissa = symb_num.div_ceil(2);
+        let mut exponent = 0;
+        while mantissa > 31 {
+            mantissa = (mantissa + 3) >> 2;
+            exponent += 1;
         }
+        Some(Self { mantissa, exponent })
+    }
+
+    /// Number of symbols the modem actually waits for
+    pub const fn symbols(&self) -> u16 {
+        (self.mantissa as u16 * 2) << (2 * self.exponent)
+    }
+
+    /// Parameter of the SetLoRaSymbNumTimeout command
+    pub(crate) const fn command_param(&self) -> u8 {
+        self.mantissa << (2 * self.exponent + 1)
+    }
+
+    /// Value of the LoRaSynchTimeout register
+    pub(crate) const fn register_value(&self) -> u8 {
+        self.exponent + (self.mantissa << 3)
     }
 }
 
+/// Number of symbols the receiver listens for in each RX window of the duty cycle
+pub const RX_DUTY_CYCLE_SYMBOLS: u64 = 4;
+
+/// RX and sleep periods of the RX duty cycle (listen mode), in steps of 15.625 μs
+#[derive(Copy, Clone)]
+pub struct RxDutyCycle {
+    pub rx_period: RxTxTimeout,
+    pub sleep_period: RxTxTimeout,
+}
+
+impl RxDutyCycle {
+    /// Derive the duty cycle periods from the LoRa modulation parameters and the number
+    /// of preamble symbols the sender uses. The RX window spans RX_DUTY_CYCLE_SYMBOLS
+    /// symbols and the sleep period is chosen such that
+    /// preamble time >= 2 * rx_period + sleep_period, so that at least one full RX window
+    /// falls within the preamble.
+    ///
+    /// Returns None for GFSK parameters or if the preamble is too short for duty cycling.
+    pub fn from_lora_preamble(mod_params: &ModParams, preamble_len: u16) -> Option<Self> {
+        let symbol_time_ns = mod_params.get_symbol_time_ns()?;
+        let rx_time_ns = RX_DUTY_CYCLE_SYMBOLS * symbol_time_ns;
+        let preamble_time_ns = preamble_len as u64 * symbol_time_ns;
+        let sleep_time_ns = preamble_time_ns.checked_sub(2 * rx_time_ns)?;
+
+        // Round the RX window up and the sleep period down
+        let rx_steps = rx_time_ns.div_ceil(RTC_STEP_NS);
+        let sleep_steps = sleep_time_ns / RTC_STEP_NS;
+        if sleep_steps == 0 || rx_steps > 0xFF_FFFF || sleep_steps > 0xFF_FFFF {
+            return None;
+        }
+
+        Some(Self {
+            rx_period: (rx_steps as u32).into(),
+            sleep_period: (sleep_steps as u32).into(),
+        })
+    }
+}
+
+/// Time on air of a LoRa packet in μs, rounded up, following section 6.1.4 of the datasheet:
+///
+/// | Spreading factor | Number of symbols                                                                    |
+/// |------------------|--------------------------------------------------------------------------------------|
+/// | SF5, SF6         | Npre + 6.25 + 8 + ceil(max(8 * PL + CRC - 4 * SF + H, 0) / (4 * SF)) * (CR + 4)     |
+/// | SF7 to SF12      | Npre + 4.25 + 8 + ceil(max(8 * PL + CRC - 4 * SF + 8 + H, 0) / (4 * SF)) * (CR + 4) |
+///
+/// CRC is 16 with the CRC on, H is 20 with an explicit header and CR runs from 1 (4/5)
+/// to 4 (4/8). With low data rate optimization the divisor becomes 4 * (SF - 2).
+/// The preamble length, header type and CRC are taken from packet_params,
+/// the payload length is passed separately.
+///
+/// Returns None for GFSK parameters
+pub fn time_on_air(
+    mod_params: &ModParams,
+    packet_params: &LoRaPacketParams,
+    payload_len: u8,
+) -> Option<u64> {
+    let lora = mod_params.lora()?;
+    let sf = lora.spread_factor() as i64;
+    let crc = match packet_params.crc_type {
+        LoRaCrcType::CrcOn => 16,
+        LoRaCrcType::CrcOff => 0,
+    };
+    let header = match packet_params.header_type {
+        LoRaHeaderType::VarLen => 20,
+        LoRaHeaderType::FixedLen => 0,
+    };
+
+    // Count in quarter symbols to keep the fractional preamble symbols exact
+    let (fixed_quarters, payload_bits) = if sf < 7 {
+        (25, 8 * payload_len as i64 + crc - 4 * sf + header)
+    } else {
+        (17, 8 * payload_len as i64 + crc - 4 * sf + 8 + header)
+    };
+    let bits_per_block = if lora.low_dr_opt() { 4 * (sf - 2) } else { 4 * sf };
+    let blocks = (payload_bits.max(0) as u64).div_ceil(bits_per_block as u64);
+    let symbols_per_block = lora.coding_rate() as u64 + 4;
+
+    let quarters = 4 * packet_params.preamble_len as u64
+        + fixed_quarters
+        + 4 * 8
+        + 4 * blocks * symbols_per_block;
+    Some((quarters * lora.symbol_time_ns()).div_ceil(4 * 1000))
+}
+
+/// Mode the modem goes into after a successful TX or RX
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum FallbackMode {
+    /// STDBY_RC, the default
+    #[default]
+    StdbyRc = 0x20,
+    /// STDBY_XOSC
+    StdbyXosc = 0x30,
+    /// FS, for the fastest turnaround between TX and RX
+    Fs = 0x40,
+}
+
 #[repr(u8)]
 #[derive(Copy, Clone)]
 pub enum RampTime {
@@ -54,6 +225,7 @@ pub enum RampTime {
     Ramp3400u = 0x07,
 }
 
+#[derive(Copy, Clone)]
 pub struct TxParams {
     power_dbm: i8,
     ramp_time: RampTime,
@@ -84,6 +256,7 @@ impl TxParams {
     /// The output power is defined as power in dBm in a range of
     /// - -17 (0xEF) to +14 (0x0E) dBm by step of 1 dB if low power PA is selected
     /// - -9 (0xF7) to +22 (0x16) dBm by step of 1 dB if high power PA is selected
+    ///
     /// Selection between high power PA and low power PA is done with the command SetPaConfig and the parameter deviceSel.
     /// By default low power PA and +14 dBm are set.
     pub fn set_power_dbm(mut self, power_dbm: i8) -> Self {
@@ -160,6 +333,11 @@ impl PaConfig {
         self.enable_pa_clamp_fix = enable;
         self
     }
+
+    /// Whether the PA clamping fix of section 15.2 is applied
+    pub fn pa_clamp_fix_enabled(&self) -> bool {
+        self.enable_pa_clamp_fix
+    }
 }
 
 #[derive(Debug)]
@@ -186,3 +364,38 @@ impl RxBufferStatus {
         self.rx_start_buffer_pointer
     }
 }
+
+/// A packet received with SX126x::read_bytes_async
+#[derive(Copy, Clone, Debug)]
+pub struct RxPacket {
+    /// Number of bytes written into the buffer
+    pub len: usize,
+    /// RSSI of the packet in dBm. Averaged over the packet in GFSK mode
+    pub rssi: f32,
+    /// SNR of the packet in dB. Only available in LoRa mode
+    pub snr: Option<f32>,
+    /// RSSI of the LoRa signal after despreading in dBm.
+    /// In GFSK mode, the RSSI measured on the sync word
+    pub signal_rssi: f32,
+}
+
+/// Result of a transmission with SX126x::write_bytes_async
+#[derive(Copy, Clone, Debug)]
+pub enum TxOutcome {
+    /// The packet was sent
+    Done,
+    /// The TX timeout elapsed before the packet was sent
+    Timeout,
+    /// The modem did not accept the SetTx command, see the contained status
+    Failed(Status),
+}
+
+impl From<IrqStatus> for TxOutcome {
+    fn from(irq_status: IrqStatus) -> Self {
+        if irq_status.tx_done() {
+            TxOutcome::Done
+        } else {
+            TxOutcome::Timeout
+        }
+    }
+}
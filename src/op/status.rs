@@ -15,14 +15,18 @@ impl core::fmt::Debug for Status {
     }
 }
 
-#[repr(u8)]
+/// Chip mode bits 6:4 of the status byte
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum ChipMode {
-    StbyRC = 0x02,
-    StbyXOSC = 0x03,
-    FS = 0x04,
-    RX = 0x05,
-    TX = 0x06,
+    /// 0x0, read while the modem is asleep and does not drive MISO
+    Sleep,
+    StbyRC,
+    StbyXOSC,
+    FS,
+    RX,
+    TX,
+    /// 0x1 or 0x7, values the datasheet leaves undefined
+    Unknown(u8),
 }
 
 #[repr(u8)]
@@ -42,15 +46,16 @@ impl From<u8> for Status {
 }
 
 impl Status {
-    pub fn chip_mode(&self) -> Option<ChipMode> {
+    pub fn chip_mode(&self) -> ChipMode {
         use ChipMode::*;
         match (self.inner & 0x70) >> 4 {
-            0x02 => Some(StbyRC),
-            0x03 => Some(StbyXOSC),
-            0x04 => Some(FS),
-            0x05 => Some(RX),
-            0x06 => Some(TX),
-            _ => None,
+            0x00 => Sleep,
+            0x02 => StbyRC,
+            0x03 => StbyXOSC,
+            0x04 => FS,
+            0x05 => RX,
+            0x06 => TX,
+            mode => Unknown(mode),
         }
     }
 
@@ -65,6 +70,14 @@ impl Status {
             _ => None,
         }
     }
+
+    /// True if the modem could not process or execute the last command
+    pub fn is_command_error(self) -> bool {
+        matches!(
+            self.command_status(),
+            Some(CommandStatus::CommandProcessingError) | Some(CommandStatus::FailureToExecute)
+        )
+    }
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -116,3 +129,113 @@ impl PacketStatus {
         self.signal_rssi_pkt as f32 / -2.0
     }
 }
+
+/// Packet status in GFSK mode
+#[derive(Copy, Clone, Debug)]
+pub struct GfskPacketStatus {
+    rx_status: GfskRxStatus,
+    rssi_sync: u8,
+    rssi_avg: u8,
+}
+
+impl From<[u8; 3]> for GfskPacketStatus {
+    fn from(b: [u8; 3]) -> Self {
+        Self {
+            rx_status: b[0].into(),
+            rssi_sync: b[1],
+            rssi_avg: b[2],
+        }
+    }
+}
+
+impl GfskPacketStatus {
+    pub fn rx_status(&self) -> GfskRxStatus {
+        self.rx_status
+    }
+
+    /// RSSI of the last packet, measured on the sync address
+    pub fn rssi_sync(&self) -> f32 {
+        self.rssi_sync as f32 / -2.0
+    }
+
+    /// RSSI averaged over the last packet
+    pub fn rssi_avg(&self) -> f32 {
+        self.rssi_avg as f32 / -2.0
+    }
+}
+
+/// Reception status of the last GFSK packet
+///
+/// | Bit | Meaning        |
+/// |-----|----------------|
+/// | 7   | Preamble error |
+/// | 6   | Sync error     |
+/// | 5   | Address error  |
+/// | 4   | CRC error      |
+/// | 3   | Length error   |
+/// | 2   | Abort error    |
+/// | 1   | Packet received|
+/// | 0   | Packet sent    |
+#[derive(Copy, Clone)]
+pub struct GfskRxStatus {
+    inner: u8,
+}
+
+impl From<u8> for GfskRxStatus {
+    fn from(b: u8) -> Self {
+        Self { inner: b }
+    }
+}
+
+impl core::fmt::Debug for GfskRxStatus {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "GfskRxStatus {{inner: {:#010b}, preamble_err: {}, sync_err: {}, address_err: {}, crc_err: {}, length_err: {}, abort_err: {}, pkt_received: {}, pkt_sent: {}}}",
+            self.inner,
+            self.preamble_err(),
+            self.sync_err(),
+            self.address_err(),
+            self.crc_err(),
+            self.length_err(),
+            self.abort_err(),
+            self.pkt_received(),
+            self.pkt_sent(),
+        )
+    }
+}
+
+impl GfskRxStatus {
+    pub fn preamble_err(self) -> bool {
+        (self.inner & 1 << 7) > 0
+    }
+
+    pub fn sync_err(self) -> bool {
+        (self.inner & 1 << 6) > 0
+    }
+
+    /// Set when the packet was dropped by the node or broadcast address filter
+    pub fn address_err(self) -> bool {
+        (self.inner & 1 << 5) > 0
+    }
+
+    pub fn crc_err(self) -> bool {
+        (self.inner & 1 << 4) > 0
+    }
+
+    pub fn length_err(self) -> bool {
+        (self.inner & 1 << 3) > 0
+    }
+
+    pub fn abort_err(self) -> bool {
+        (self.inner & 1 << 2) > 0
+    }
+
+    pub fn pkt_received(self) -> bool {
+        (self.inner & 1 << 1) > 0
+    }
+
+    pub fn pkt_sent(self) -> bool {
+        (self.inner & 1 << 0) > 0
+    }
+}
@@ -1,6 +1,7 @@
 //! Registers as defined in chapter 12
 #[allow(dead_code)]
 #[repr(u16)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 /// Every register defined in the SX126X datasheet
 /// See table 12-1 in the datasheet
 pub enum Register {
@@ -49,6 +50,8 @@ pub enum Register {
     NodeAddress = 0x06CD,
     /// Broadcast Address used in FSK mode
     BroadcastAddress = 0x06CE,
+    /// Mantissa and exponent of the LoRa symbol number timeout
+    LoRaSynchTimeout = 0x0706,
     /// Optimize the inverted IQ operation
     IqPolaritySetup = 0x0736,
     /// Differantiate the LoRa signal for Public or Private Network; MSB
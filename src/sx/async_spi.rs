@@ -0,0 +1,113 @@
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::SpiDevice;
+
+use super::driver::{AwaitDio1, SxDriver};
+use super::err::{PinError, SxError};
+use super::flow;
+use super::transport::{AsyncTransport, Transport};
+use super::wait::AnyWait;
+use super::{Pins, TestTx};
+use crate::op::*;
+
+/// Wrapper around a Semtech SX1261/62 LoRa modem, connected to an async SPI bus.
+/// Offers the same commands as SX126x, but every SPI transfer is awaited, so a
+/// DMA-backed SPI peripheral can yield to the executor while the data is clocked out.
+pub type SX126xAsync<TSPI, TNRST, TBUSY, TANT, TDIO1> =
+    SxDriver<AsyncTransport<TSPI, TNRST, TBUSY>, TANT, TDIO1>;
+
+impl<TSPI, TNRST, TBUSY, TANT, TDIO1, TSPIERR, TPINERR> SX126xAsync<TSPI, TNRST, TBUSY, TANT, TDIO1>
+where
+    TPINERR: core::fmt::Debug,
+    TSPI: SpiDevice<Error = TSPIERR>,
+    TNRST: OutputPin<Error = TPINERR>,
+    TBUSY: AnyWait<Error = TPINERR>,
+    TANT: OutputPin<Error = TPINERR>,
+    TDIO1: AnyWait<Error = TPINERR>,
+{
+    // Create a new SX126xAsync
+    pub fn new(spi: TSPI, pins: Pins<TNRST, TBUSY, TANT, TDIO1>) -> Self {
+        let (nrst_pin, busy_pin, ant_pin, dio1_pin) = pins;
+        Self::with_transport(AsyncTransport::new(spi, nrst_pin, busy_pin), ant_pin, dio1_pin)
+    }
+
+    /// Start transmitting an unmodulated carrier at the configured frequency and output power.
+    /// The transmission runs until TestTx::stop is called. Dropping the TestTx instead
+    /// leaves the modem transmitting until the next command of this driver
+    pub async fn set_tx_continuous_wave(
+        &mut self,
+    ) -> Result<TestTx<'_, Self>, SxError<TSPIERR, TPINERR>> {
+        let previous_mode = self
+            .start_test_tx(OperatingModes::SetTxContinuousWave)
+            .await?;
+        Ok(TestTx::new(self, previous_mode, Self::abandon_test_tx))
+    }
+
+    /// Start transmitting an infinite sequence of preamble symbols, using the configured
+    /// modulation. The transmission runs until TestTx::stop is called, see
+    /// SX126xAsync::set_tx_continuous_wave
+    pub async fn set_tx_infinite_preamble(
+        &mut self,
+    ) -> Result<TestTx<'_, Self>, SxError<TSPIERR, TPINERR>> {
+        let previous_mode = self
+            .start_test_tx(OperatingModes::SetTxInfinitePreamble)
+            .await?;
+        Ok(TestTx::new(self, previous_mode, Self::abandon_test_tx))
+    }
+
+    /// High level method to send a message. This methods writes the data in the buffer,
+    /// puts the device in TX mode, and waits until the devices
+    /// is done sending the data or a timeout occurs.
+    /// Please note that this method updates the packet params
+    pub async fn write_bytes(
+        &mut self,
+        data: &[u8],
+        timeout: RxTxTimeout,
+        params: impl Into<PacketParams>,
+    ) -> Result<TxOutcome, SxError<TSPIERR, TPINERR>> {
+        self.transmit::<AwaitDio1>(data, timeout, params.into())
+            .await
+    }
+
+    /// High level method to receive a message, see SX126x::read_bytes_async
+    pub async fn read_bytes(
+        &mut self,
+        buf: &mut [u8],
+        timeout: RxTxTimeout,
+    ) -> Result<RxPacket, SxError<TSPIERR, TPINERR>> {
+        self.receive::<AwaitDio1>(buf, timeout).await
+    }
+
+    /// Wait for the busy pin to go low
+    pub async fn wait_on_busy(&mut self) -> Result<(), SxError<TSPIERR, TPINERR>> {
+        self.bus.wait_on_busy().await
+    }
+
+    /// Wait for the dio1 pin to go high
+    pub async fn wait_on_dio1(&mut self) -> Result<(), PinError<TPINERR>> {
+        self.dio1_pin
+            .anywait_for_high()
+            .await
+            .map_err(PinError::Input)
+    }
+}
+
+impl<'a, TSPI, TNRST, TBUSY, TANT, TDIO1, TSPIERR, TPINERR>
+    TestTx<'a, SX126xAsync<TSPI, TNRST, TBUSY, TANT, TDIO1>>
+where
+    TPINERR: core::fmt::Debug,
+    TSPI: SpiDevice<Error = TSPIERR>,
+    TNRST: OutputPin<Error = TPINERR>,
+    TBUSY: AnyWait<Error = TPINERR>,
+    TANT: OutputPin<Error = TPINERR>,
+    TDIO1: AnyWait<Error = TPINERR>,
+{
+    /// Stop the test transmission. The modem is put in STDBY_RC, and from there
+    /// back into STDBY_XOSC or FS if it was in one of those modes before the test
+    pub async fn stop(mut self) -> Result<(), SxError<TSPIERR, TPINERR>> {
+        let result = self
+            .sx
+            .run_flow(flow::stop_test_tx(self.previous_mode))
+            .await;
+        self.stopped(result)
+    }
+}
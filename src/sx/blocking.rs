@@ -0,0 +1,128 @@
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::{InputPin, OutputPin};
+use embedded_hal::spi::SpiDevice;
+
+use super::driver::Dio1Wait;
+use super::err::SxError;
+use super::flow;
+use super::transport::{BlockingTransport, Transport};
+use super::{block_on, SX126x, SxRng, TestTx};
+use crate::conf::Config;
+use crate::op::*;
+
+/// Poll DIO1 with the delay of the blocking transport, giving up after the timeout
+pub(super) struct PollDio1;
+
+impl<TSPI, TNRST, TBUSY, TDELAY, TDIO1, TSPIERR, TPINERR> Dio1Wait<BlockingTransport<TSPI, TNRST, TBUSY, TDELAY>, TDIO1>
+    for PollDio1
+where
+    TSPI: SpiDevice<Error = TSPIERR>,
+    TNRST: OutputPin<Error = TPINERR>,
+    TBUSY: InputPin<Error = TPINERR>,
+    TDIO1: InputPin<Error = TPINERR>,
+    TDELAY: DelayNs,
+{
+    async fn wait_on_dio1(
+        bus: &mut BlockingTransport<TSPI, TNRST, TBUSY, TDELAY>,
+        dio1_pin: &mut TDIO1,
+        timeout_us: Option<u32>,
+    ) -> Result<(), SxError<TSPIERR, TPINERR>> {
+        bus.poll_dio1(dio1_pin, timeout_us)
+    }
+}
+
+impl<TSPI, TNRST, TBUSY, TANT, TDIO1, TDELAY, TSPIERR, TPINERR> SX126x<TSPI, TNRST, TBUSY, TANT, TDIO1, TDELAY>
+where
+    TPINERR: core::fmt::Debug,
+    TSPI: SpiDevice<Error = TSPIERR>,
+    TNRST: OutputPin<Error = TPINERR>,
+    TBUSY: InputPin<Error = TPINERR>,
+    TANT: OutputPin<Error = TPINERR>,
+    TDIO1: InputPin<Error = TPINERR>,
+    TDELAY: DelayNs,
+{
+    /// Initialize and configure the SX126x using the provided Config.
+    /// Blocking counterpart of SX126x::init_async
+    pub fn init(&mut self, conf: Config) -> Result<(), SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.init(conf))
+    }
+
+    /// Wake the modem from sleep by toggling NSS.
+    /// Blocking counterpart of SX126x::wake_async
+    pub fn wake(&mut self) -> Result<(), SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.wake())
+    }
+
+    /// High level method to send a message. Blocking counterpart of SX126x::write_bytes_async.
+    /// Waits on DIO1 until the packet is sent or the timeout elapsed. If DIO1 stays low
+    /// for 100 ms longer than the timeout, or than the time on air of the packet
+    /// if no timeout is set, this method fails with SxError::Timeout.
+    /// Please note that this method updates the packet params
+    pub fn write_bytes(
+        &mut self,
+        data: &[u8],
+        timeout: RxTxTimeout,
+        params: impl Into<PacketParams>,
+    ) -> Result<TxOutcome, SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.transmit::<PollDio1>(data, timeout, params.into()))
+    }
+
+    /// High level method to receive a message. Blocking counterpart of SX126x::read_bytes_async.
+    /// Waits on DIO1 until a packet is received or the timeout elapsed. The timer of the
+    /// modem may stop once a preamble is detected, so this method fails with
+    /// SxError::Timeout only if DIO1 stays low for the timeout, the time on air of
+    /// the longest packet and 100 ms together. Without a timeout, or in continuous RX,
+    /// it waits until a packet is received.
+    pub fn read_bytes(
+        &mut self,
+        buf: &mut [u8],
+        timeout: RxTxTimeout,
+    ) -> Result<RxPacket, SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.receive::<PollDio1>(buf, timeout))
+    }
+
+    /// Read a 32-bit random number from the modem.
+    /// Blocking counterpart of SX126x::random_u32_async
+    pub fn random_u32(&mut self) -> Result<u32, SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.random_u32())
+    }
+
+    /// Use the modem as a random number generator implementing rand_core::RngCore
+    pub fn rng(&mut self) -> SxRng<'_, Self> {
+        SxRng::new(self)
+    }
+
+    /// Poll the busy pin until it goes low. Fails with SxError::Timeout
+    /// if the modem stays busy for longer than 100 ms
+    pub fn wait_on_busy(&mut self) -> Result<(), SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.bus.wait_on_busy())
+    }
+
+    /// Poll the dio1 pin until it goes high. Fails with SxError::Timeout
+    /// after timeout_us μs, or waits indefinitely if no timeout is given
+    pub fn wait_on_dio1(&mut self, timeout_us: Option<u32>) -> Result<(), SxError<TSPIERR, TPINERR>> {
+        self.driver.bus.poll_dio1(&mut self.driver.dio1_pin, timeout_us)
+    }
+}
+
+impl<'a, TSPI, TNRST, TBUSY, TANT, TDIO1, TDELAY, TSPIERR, TPINERR>
+    TestTx<'a, SX126x<TSPI, TNRST, TBUSY, TANT, TDIO1, TDELAY>>
+where
+    TPINERR: core::fmt::Debug,
+    TSPI: SpiDevice<Error = TSPIERR>,
+    TNRST: OutputPin<Error = TPINERR>,
+    TBUSY: InputPin<Error = TPINERR>,
+    TANT: OutputPin<Error = TPINERR>,
+    TDIO1: InputPin<Error = TPINERR>,
+    TDELAY: DelayNs,
+{
+    /// Stop the test transmission. Blocking counterpart of TestTx::stop_async
+    pub fn stop(mut self) -> Result<(), SxError<TSPIERR, TPINERR>> {
+        let result = block_on(
+            self.sx
+                .driver
+                .run_flow(flow::stop_test_tx(self.previous_mode)),
+        );
+        self.stopped(result)
+    }
+}
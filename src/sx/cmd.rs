@@ -0,0 +1,268 @@
+//! Encoding of the commands detailed in chapter 13, shared by
+//! the blocking and the async SPI drivers. Every function returns the bytes
+//! the host clocks out: the opcode, followed by the parameters and the NOPs
+//! that clock in the response of the modem.
+use super::NOP;
+use crate::op::*;
+use crate::reg::Register;
+
+pub(crate) fn set_sleep(sleep_config: SleepConfig) -> [u8; 2] {
+    [OperatingModes::SetSleep.into(), sleep_config.into()]
+}
+
+pub(crate) fn set_standby(standby_config: StandbyConfig) -> [u8; 2] {
+    [OperatingModes::SetStandby.into(), standby_config as u8]
+}
+
+pub(crate) fn set_regulator_mode(regulator_mode: RegulatorMode) -> [u8; 2] {
+    [
+        OperatingModes::SetRegulatorMode.into(),
+        regulator_mode as u8,
+    ]
+}
+
+pub(crate) fn set_rx_tx_fallback_mode(fallback_mode: FallbackMode) -> [u8; 2] {
+    [
+        OperatingModes::SetRxTxFallbackMode.into(),
+        fallback_mode as u8,
+    ]
+}
+
+pub(crate) fn set_packet_type(packet_type: PacketType) -> [u8; 2] {
+    [0x8A, packet_type as u8]
+}
+
+/// Response: RFU, status, packetType
+pub(crate) fn get_packet_type() -> [u8; 3] {
+    [0x11, NOP, NOP]
+}
+
+pub(crate) fn set_cad_params(cad_params: CadParams) -> [u8; 8] {
+    let (timeout_high, timeout_mid, timeout_low) = match cad_params.timeout {
+        Some(timeout) => CadTimeout::split_u24(timeout.into()),
+        None => (NOP, NOP, NOP),
+    };
+
+    [
+        CadCommands::SetCadParams.into(),
+        cad_params.symbol_num.into(),
+        cad_params.det_peak.into(),
+        cad_params.det_min.into(),
+        cad_params.exit_mode.into(),
+        timeout_high,
+        timeout_mid,
+        timeout_low,
+    ]
+}
+
+/// Commands without parameters: SetCad, SetFs, SetTxContinuousWave and SetTxInfinitePreamble.
+/// The NOP clocks out the status byte
+pub(crate) fn operating_mode(opcode: OperatingModes) -> [u8; 2] {
+    [opcode.into(), NOP]
+}
+
+/// Response: RFU, status
+pub(crate) fn get_status() -> [u8; 2] {
+    [0xC0, NOP]
+}
+
+/// Response: RFU, status, 6 bytes of statistics
+pub(crate) fn get_stats() -> [u8; 8] {
+    [0x10, NOP, NOP, NOP, NOP, NOP, NOP, NOP]
+}
+
+pub(crate) fn calibrate_image(freq: CalibImageFreq) -> [u8; 3] {
+    let freq: [u8; 2] = freq.into();
+    [OperatingModes::CalibrateImage.into(), freq[0], freq[1]]
+}
+
+pub(crate) fn calibrate(calib_param: CalibParam) -> [u8; 2] {
+    [OperatingModes::Calibrate.into(), calib_param.into()]
+}
+
+/// Header of WriteRegister, followed by the data
+pub(crate) fn write_register(register: Register) -> [u8; 3] {
+    let [msb, lsb] = (register as u16).to_be_bytes();
+    [0x0D, msb, lsb]
+}
+
+/// Header of ReadRegister, including the NOP that clocks out the status byte.
+/// The register data is read after it
+pub(crate) fn read_register(register: Register) -> [u8; 4] {
+    let [msb, lsb] = (register as u16).to_be_bytes();
+    [0x1D, msb, lsb, NOP]
+}
+
+/// Header of WriteBuffer, followed by the data
+pub(crate) fn write_buffer(offset: u8) -> [u8; 2] {
+    [0x0E, offset]
+}
+
+/// Header of ReadBuffer, including the NOP that clocks out the status byte.
+/// The buffer data is read after it
+pub(crate) fn read_buffer(offset: u8) -> [u8; 3] {
+    [0x1E, offset, NOP]
+}
+
+pub(crate) fn set_dio2_as_rf_switch_ctrl(enable: bool) -> [u8; 2] {
+    [0x9D, enable as u8]
+}
+
+/// Header of GetPacketStatus, the 3 status bytes are read after it
+pub(crate) fn get_packet_status() -> [u8; 2] {
+    [0x14, NOP]
+}
+
+pub(crate) fn set_dio3_as_tcxo_ctrl(tcxo_voltage: TcxoVoltage, tcxo_delay: TcxoDelay) -> [u8; 5] {
+    let tcxo_delay: [u8; 3] = tcxo_delay.into();
+    [
+        0x97,
+        tcxo_voltage as u8,
+        tcxo_delay[0],
+        tcxo_delay[1],
+        tcxo_delay[2],
+    ]
+}
+
+pub(crate) fn clear_device_errors() -> [u8; 3] {
+    [0x07, NOP, NOP]
+}
+
+/// Response: RFU, status, OpError
+pub(crate) fn get_device_errors() -> [u8; 4] {
+    [0x17, NOP, NOP, NOP]
+}
+
+pub(crate) fn set_dio_irq_params(
+    irq_mask: IrqMask,
+    dio1_mask: IrqMask,
+    dio2_mask: IrqMask,
+    dio3_mask: IrqMask,
+) -> [u8; 9] {
+    let mut buf = [0x08; 9];
+    for (i, mask) in [irq_mask, dio1_mask, dio2_mask, dio3_mask]
+        .into_iter()
+        .enumerate()
+    {
+        let mask = Into::<u16>::into(mask).to_be_bytes();
+        buf[1 + 2 * i..3 + 2 * i].copy_from_slice(&mask);
+    }
+    buf
+}
+
+/// Header of GetIrqStatus, the status is clocked out during the NOP and the IRQ flags are read after it
+pub(crate) fn get_irq_status() -> [u8; 2] {
+    [0x12, NOP]
+}
+
+pub(crate) fn clear_irq_status(mask: IrqMask) -> [u8; 3] {
+    let [msb, lsb] = Into::<u16>::into(mask).to_be_bytes();
+    [0x02, msb, lsb]
+}
+
+pub(crate) fn set_tx(timeout: RxTxTimeout) -> [u8; 4] {
+    let timeout: [u8; 3] = timeout.into();
+    [
+        OperatingModes::SetTx.into(),
+        timeout[0],
+        timeout[1],
+        timeout[2],
+    ]
+}
+
+pub(crate) fn set_rx(timeout: RxTxTimeout) -> [u8; 4] {
+    let timeout: [u8; 3] = timeout.into();
+    [
+        OperatingModes::SetRx.into(),
+        timeout[0],
+        timeout[1],
+        timeout[2],
+    ]
+}
+
+pub(crate) fn stop_timer_on_preamble(enable: bool) -> [u8; 2] {
+    [OperatingModes::StopTimerOnPreamble.into(), enable as u8]
+}
+
+pub(crate) fn set_lora_symb_num_timeout(timeout: LoRaSymbNumTimeout) -> [u8; 2] {
+    [0xA0, timeout.command_param()]
+}
+
+pub(crate) fn set_rx_duty_cycle(rx_period: RxTxTimeout, sleep_period: RxTxTimeout) -> [u8; 7] {
+    let rx_period: [u8; 3] = rx_period.into();
+    let sleep_period: [u8; 3] = sleep_period.into();
+    let mut buf = [OperatingModes::SetRxDutyCycle.into(); 7];
+    buf[1..4].copy_from_slice(&rx_period);
+    buf[4..].copy_from_slice(&sleep_period);
+    buf
+}
+
+pub(crate) fn set_packet_params(params: &PacketParams) -> [u8; 10] {
+    let bytes: [u8; 9] = params.into();
+    let mut buf = [0x8C; 10];
+    buf[1..].copy_from_slice(&bytes);
+    buf
+}
+
+pub(crate) fn set_mod_params(params: &ModParams, xtal: Frequency) -> [u8; 9] {
+    let bytes = params.to_bytes(xtal);
+    let mut buf = [0x8B; 9];
+    buf[1..].copy_from_slice(&bytes);
+    buf
+}
+
+pub(crate) fn set_tx_params(params: &TxParams) -> [u8; 3] {
+    let bytes: [u8; 2] = params.into();
+    [0x8E, bytes[0], bytes[1]]
+}
+
+pub(crate) fn set_rf_frequency(rf_freq: u32) -> [u8; 5] {
+    let bytes = rf_freq.to_be_bytes();
+    [0x86, bytes[0], bytes[1], bytes[2], bytes[3]]
+}
+
+/// SetPaConfig, sending paDutyCycle, hpMax, deviceSel and paLut
+pub(crate) fn set_pa_config(pa_config: &PaConfig) -> [u8; 5] {
+    let bytes: [u8; 4] = pa_config.into();
+    [
+        OperatingModes::SetPaConfig.into(),
+        bytes[0],
+        bytes[1],
+        bytes[2],
+        bytes[3],
+    ]
+}
+
+pub(crate) fn set_buffer_base_address(tx_base_addr: u8, rx_base_addr: u8) -> [u8; 3] {
+    [0x8F, tx_base_addr, rx_base_addr]
+}
+
+/// Response: RFU, status, PayloadLengthRx, RxStartBufferPointer
+pub(crate) fn get_rx_buffer_status() -> [u8; 4] {
+    [0x13, NOP, NOP, NOP]
+}
+
+/// New value of the TxClampConfig register, with the PA clamping fix
+/// of section 15.2 applied or removed
+pub(crate) fn tx_clamp_config(clamp_config: u8, apply_fix: bool) -> u8 {
+    if apply_fix {
+        clamp_config | 0x1E
+    } else {
+        // Remove fix (This is default behavior)
+        (clamp_config & !0x1E) | 0x08
+    }
+}
+
+/// New value of the SensitivityConfig register. Section 15.1: the modulation quality
+/// fix is applied for LoRa with a 500 kHz bandwidth and removed otherwise
+pub(crate) fn sensitivity_config(
+    sensitivity_config: u8,
+    packet_type: PacketType,
+    mod_params: &ModParams,
+) -> u8 {
+    if packet_type == PacketType::LoRa && mod_params.get_bandwidth() == Some(LoRaBandWidth::BW500) {
+        sensitivity_config & 0xFB
+    } else {
+        sensitivity_config | 0x04
+    }
+}
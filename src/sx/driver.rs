@@ -0,0 +1,1064 @@
+use core::convert::TryInto;
+use embedded_hal::digital::OutputPin;
+use embedded_hal::spi::Operation;
+
+use super::err::{PinError, RxError, SxError};
+use super::flow::{self, Step};
+use super::transport::Transport;
+use super::wait::AnyWait;
+use super::{
+    check_rx, cmd, gfsk_rx_packet, lora_rx_packet, rx_finished, sequence_status, tx_finished,
+    woke_from_cold_start, NOP,
+};
+use crate::conf::Config;
+use crate::op::*;
+use crate::reg::*;
+
+/// Time granted on top of the expected duration of a TX or RX before
+/// SX126x::write_bytes and SX126x::read_bytes give up on DIO1, in μs
+const DIO1_MARGIN_US: u32 = 100_000;
+
+/// The driver logic for a Semtech SX1261/62 LoRa modem, generic over the Transport
+/// that connects it to the modem. SX126xAsync is an SxDriver over an AsyncTransport,
+/// and SX126x wraps one over a BlockingTransport, whose commands never await.
+pub struct SxDriver<T, TANT, TDIO1> {
+    pub(super) bus: T,
+    ant_pin: TANT,
+    pub(super) dio1_pin: TDIO1,
+    pub(super) config: Config,
+    sleep_config: Option<SleepConfig>,
+    /// Band of the last image calibration, None if unknown
+    calib_image: Option<CalibImageFreq>,
+    /// Turn command errors reported in the status byte into SxError::Command
+    check_status: bool,
+    /// A test transmission was dropped without being stopped, so the modem
+    /// is still transmitting until SetStandby is sent
+    test_tx_pending: bool,
+}
+
+/// How the write_bytes and read_bytes flows wait for DIO1 to go high
+pub(super) trait Dio1Wait<T: Transport, TDIO1> {
+    /// Wait until DIO1 is high. Fails with SxError::Timeout after timeout_us μs
+    /// if the strategy can keep time, otherwise the timeout is ignored
+    async fn wait_on_dio1(
+        bus: &mut T,
+        dio1_pin: &mut TDIO1,
+        timeout_us: Option<u32>,
+    ) -> Result<(), SxError<T::SpiError, T::PinError>>;
+}
+
+/// Await DIO1 through AnyWait, without a timeout
+pub(super) struct AwaitDio1;
+
+impl<T, TDIO1> Dio1Wait<T, TDIO1> for AwaitDio1
+where
+    T: Transport,
+    TDIO1: AnyWait<Error = T::PinError>,
+{
+    async fn wait_on_dio1(
+        _bus: &mut T,
+        dio1_pin: &mut TDIO1,
+        _timeout_us: Option<u32>,
+    ) -> Result<(), SxError<T::SpiError, T::PinError>> {
+        dio1_pin
+            .anywait_for_high()
+            .await
+            .map_err(|err| SxError::Pin(PinError::Input(err)))
+    }
+}
+
+impl<T, TANT, TDIO1> SxDriver<T, TANT, TDIO1>
+where
+    T: Transport,
+    TANT: OutputPin<Error = T::PinError>,
+{
+    // Create a new driver on top of the given transport
+    pub fn with_transport(bus: T, ant_pin: TANT, dio1_pin: TDIO1) -> Self {
+        Self {
+            bus,
+            ant_pin,
+            dio1_pin,
+            config: Config::default(),
+            sleep_config: None,
+            calib_image: None,
+            check_status: false,
+            test_tx_pending: false,
+        }
+    }
+
+    /// Fail with SxError::Command on command errors, see SX126x::set_check_status
+    pub fn set_check_status(&mut self, enabled: bool) {
+        self.check_status = enabled;
+    }
+
+    /// Send a command and decode the status byte the modem clocks out
+    /// while the first parameter is sent
+    async fn command(&mut self, buf: &mut [u8]) -> Result<Status, SxError<T::SpiError, T::PinError>> {
+        debug_assert!(buf.len() > 1);
+        self.transaction(&mut [Operation::TransferInPlace(buf)])
+            .await?;
+        self.check(buf[1].into())
+    }
+
+    /// Run an SPI transaction once the modem is ready to accept a command.
+    /// A sleeping modem would only wake up and drop the command, so this fails
+    /// with SxError::Asleep until the modem is woken up. A test transmission
+    /// that was dropped without being stopped is ended with SetStandby first
+    async fn transaction(
+        &mut self,
+        operations: &mut [Operation<'_, u8>],
+    ) -> Result<(), SxError<T::SpiError, T::PinError>> {
+        if self.sleep_config.is_some() {
+            return Err(SxError::Asleep);
+        }
+        if self.test_tx_pending {
+            let mut standby = cmd::set_standby(StandbyConfig::StbyRc);
+            self.bus
+                .transaction(&mut [Operation::TransferInPlace(&mut standby)])
+                .await?;
+            self.bus.wait_on_busy().await?;
+            self.test_tx_pending = false;
+        }
+        self.bus.transaction(operations).await
+    }
+
+    /// Fail with SxError::Command if status checking is enabled and status reports an error
+    fn check(&self, status: Status) -> Result<Status, SxError<T::SpiError, T::PinError>> {
+        if self.check_status && status.is_command_error() {
+            return Err(SxError::Command(status));
+        }
+        Ok(status)
+    }
+
+    // Initialize and configure the SX126x using the provided Config
+    pub async fn init(&mut self, conf: Config) -> Result<(), SxError<T::SpiError, T::PinError>> {
+        // Reset the sx
+        self.reset().await?;
+        self.bus.wait_on_busy().await?;
+
+        // Save the config for later use
+        self.config = conf;
+
+        self.configure().await
+    }
+
+    /// Apply the stored Config to the modem. Used by init and to restore
+    /// the configuration after a cold start
+    async fn configure(&mut self) -> Result<(), SxError<T::SpiError, T::PinError>> {
+        let conf = self.config.clone();
+        self.run_flow(flow::configure(&conf)).await
+    }
+
+    /// Run the steps of a flow, waiting on BUSY after each one
+    pub(super) async fn run_flow<'a>(
+        &mut self,
+        steps: impl Iterator<Item = Step<'a>>,
+    ) -> Result<(), SxError<T::SpiError, T::PinError>> {
+        for step in steps {
+            self.run_step(step).await?;
+            self.bus.wait_on_busy().await?;
+        }
+        Ok(())
+    }
+
+    /// Send the command of a single flow step. The modem is busy afterwards,
+    /// so the caller has to wait on BUSY before running the next step
+    async fn run_step(&mut self, step: Step<'_>) -> Result<(), SxError<T::SpiError, T::PinError>> {
+        match step {
+            Step::Standby(standby_config) => self.set_standby(standby_config).await,
+            Step::RegulatorMode(regulator_mode) => self.set_regulator_mode(regulator_mode).await,
+            Step::PacketType(packet_type) => self.set_packet_type(packet_type).await,
+            Step::RfFrequency(rf_frequency) => self.set_rf_frequency(rf_frequency).await,
+            Step::Dio3AsTcxoCtrl(tcxo_voltage, tcxo_delay) => {
+                self.set_dio3_as_tcxo_ctrl(tcxo_voltage, tcxo_delay).await
+            }
+            Step::Calibrate(calib_param) => self.calibrate(calib_param).await,
+            Step::CalibrateImage(freq) => self.calibrate_image(freq).await,
+            Step::PaConfig(pa_config) => self.set_pa_config(pa_config).await,
+            Step::TxParams(tx_params) => self.set_tx_params(tx_params).await,
+            Step::BufferBaseAddress(tx_base_addr, rx_base_addr) => {
+                self.set_buffer_base_address(tx_base_addr, rx_base_addr)
+                    .await
+            }
+            Step::FallbackMode(fallback_mode) => self.set_rx_tx_fallback_mode(fallback_mode).await,
+            Step::ModParams(mod_params) => self.set_mod_params(mod_params).await,
+            Step::PacketParams(packet_params) => self.set_packet_params(packet_params).await,
+            Step::GfskNodeAddress(address) => self.set_gfsk_node_address(address).await,
+            Step::GfskBroadcastAddress(address) => self.set_gfsk_broadcast_address(address).await,
+            Step::GfskSyncWord(sync_word) => self.set_gfsk_sync_word(sync_word.as_bytes()).await,
+            Step::GfskCrc(crc) => self.set_gfsk_crc(crc).await,
+            Step::WhiteningSeed(seed) => self.set_whitening_seed(seed).await,
+            // Not through set_dio_irq_params, flows like start_random apply
+            // masks that must not replace the ones in the stored Config
+            Step::DioIrqParams(irq_mask, dio1_mask, dio2_mask, dio3_mask) => {
+                self.command(&mut cmd::set_dio_irq_params(
+                    irq_mask, dio1_mask, dio2_mask, dio3_mask,
+                ))
+                .await
+            }
+            Step::Dio2AsRfSwitchCtrl(enable) => self.set_dio2_as_rf_switch_ctrl(enable).await,
+            Step::SyncWord(sync_word) => self.set_sync_word(sync_word).await,
+            Step::WriteBuffer(offset, data) => self.write_buffer(offset, data).await,
+            Step::ClearIrqStatus(mask) => self.clear_irq_status(mask).await,
+            Step::FixSensitivity => self.fix_sensitivity().await,
+            Step::Rx(timeout) => self.set_rx(timeout).await,
+            Step::Fs => self.set_fs().await,
+            Step::ClearDeviceErrors => self.clear_device_errors().await,
+            Step::CheckDeviceErrors => return self.check_device_errors().await,
+        }?;
+        Ok(())
+    }
+
+    /// Fail with SxError::Device if the modem reports any error
+    async fn check_device_errors(&mut self) -> Result<(), SxError<T::SpiError, T::PinError>> {
+        let errors = self.get_device_errors().await?;
+        if errors.any() {
+            return Err(SxError::Device(errors));
+        }
+        Ok(())
+    }
+
+    /// Put the modem in sleep mode. Commands fail with SxError::Asleep until
+    /// the modem is woken up again with SX126xAsync::wake
+    pub async fn sleep(
+        &mut self,
+        sleep_config: SleepConfig,
+    ) -> Result<(), SxError<T::SpiError, T::PinError>> {
+        self.command(&mut cmd::set_sleep(sleep_config)).await?;
+        self.sleep_config = Some(sleep_config);
+        Ok(())
+    }
+
+    /// Wake the modem from sleep by toggling NSS, and wait until it is
+    /// ready in STDBY_RC. After a cold start the stored Config, which the
+    /// setters keep up to date, is applied again
+    pub async fn wake(&mut self) -> Result<(), SxError<T::SpiError, T::PinError>> {
+        // Any transaction pulls NSS low, which wakes the modem
+        self.bus.write(&cmd::get_status()).await?;
+        self.bus.wait_on_busy().await?;
+
+        if woke_from_cold_start(self.sleep_config.take()) {
+            // All configuration is lost after a cold start
+            self.calib_image = None;
+            self.configure().await?;
+        }
+        Ok(())
+    }
+
+    /// Set the LoRa Sync word
+    /// Use 0x3444 for public networks like TTN
+    /// Use 0x1424 for private networks
+    pub async fn set_sync_word(
+        &mut self,
+        sync_word: u16,
+    ) -> Result<Status, SxError<T::SpiError, T::PinError>> {
+        let status = self
+            .write_register(Register::LoRaSyncWordMsb, &sync_word.to_be_bytes())
+            .await?;
+        self.config.sync_word = sync_word;
+        Ok(status)
+    }
+
+    /// Set the GFSK sync word, 1 to 8 bytes long. The number of bits the
+    /// modem actually uses is set with GfskPacketParams::sync_word_len
+    pub async fn set_gfsk_sync_word(
+        &mut self,
+        sync_word: &[u8],
+    ) -> Result<Status, SxError<T::SpiError, T::PinError>> {
+        let sync_word = GfskSyncWord::new(sync_word).ok_or(SxError::InvalidConfig(
+            ConfigError::GfskSyncWordLength(sync_word.len()),
+        ))?;
+        let status = self
+            .write_register(Register::SyncWord0, sync_word.as_bytes())
+            .await?;
+        self.config.gfsk_sync_word = Some(sync_word);
+        Ok(status)
+    }
+
+    /// Set the polynomial and initial value used to compute the GFSK CRC
+    pub async fn set_gfsk_crc(
+        &mut self,
+        crc: GfskCrc,
+    ) -> Result<Status, SxError<T::SpiError, T::PinError>> {
+        let status = self
+            .write_register(
+                Register::CrcMsbInitialValue,
+                &crc.initial_value.to_be_bytes(),
+            )
+            .await?;
+        let then = self
+            .write_register(
+                Register::CrcMsbPolynomialValue,
+                &crc.polynomial.to_be_bytes(),
+            )
+            .await?;
+        self.config.gfsk_crc = Some(crc);
+        Ok(sequence_status(status, then))
+    }
+
+    /// Set the 9-bit initial value of the GFSK whitening LFSR.
+    /// The 7 MSB of the WhiteningInitialValueMsb register are preserved
+    pub async fn set_whitening_seed(
+        &mut self,
+        seed: u16,
+    ) -> Result<Status, SxError<T::SpiError, T::PinError>> {
+        if seed > 0x01FF {
+            return Err(SxError::InvalidConfig(
+                ConfigError::WhiteningSeedOutOfRange(seed),
+            ));
+        }
+        let bytes = seed.to_be_bytes();
+        let (status, _) = self
+            .modify_reg(Register::WhiteningInitialValueMsb, |msb| {
+                (msb & 0xFE) | (bytes[0] & 0x01)
+            })
+            .await?;
+        let then = self
+            .write_register(Register::WhiteningInitialValueLsb, &[bytes[1]])
+            .await?;
+        self.config.whitening_seed = Some(seed);
+        Ok(sequence_status(status, then))
+    }
+
+    /// Set the node address used for GFSK address filtering
+    pub async fn set_gfsk_node_address(
+        &mut self,
+        address: u8,
+    ) -> Result<Status, SxError<T::SpiError, T::PinError>> {
+        let status = self
+            .write_register(Register::NodeAddress, &[address])
+            .await?;
+        self.config.gfsk_address.node = address;
+        Ok(status)
+    }
+
+    /// Set the broadcast address used for GFSK address filtering
+    pub async fn set_gfsk_broadcast_address(
+        &mut self,
+        address: u8,
+    ) -> Result<Status, SxError<T::SpiError, T::PinError>> {
+        let status = self
+            .write_register(Register::BroadcastAddress, &[address])
+            .await?;
+        self.config.gfsk_address.broadcast = address;
+        Ok(status)
+    }
+
+    /// Set the modem packet type, which can be either GFSK of LoRa
+    pub async fn set_packet_type(
+        &mut self,
+        packet_type: PacketType,
+    ) -> Result<Status, SxError<T::SpiError, T::PinError>> {
+        let status = self.command(&mut cmd::set_packet_type(packet_type)).await?;
+        self.config.packet_type = packet_type;
+        Ok(status)
+    }
+
+    /// The command GetPacketType() returns the current operating packet type of the radio.
+    pub async fn get_packet_type(&mut self) -> Result<PacketType, SxError<T::SpiError, T::PinError>> {
+        let mut result = cmd::get_packet_type();
+        self.transaction(&mut [Operation::TransferInPlace(&mut result)])
+            .await?;
+        self.check(result[1].into())?;
+        Ok(result[2].try_into()?)
+    }
+
+    /// Put the modem in standby mode
+    pub async fn set_standby(
+        &mut self,
+        standby_config: StandbyConfig,
+    ) -> Result<Status, SxError<T::SpiError, T::PinError>> {
+        self.command(&mut cmd::set_standby(standby_config)).await
+    }
+
+    /// Select the regulator used by the modem. Only use RegulatorMode::DcDc
+    /// if the DC-DC inductor is fitted on the board
+    pub async fn set_regulator_mode(
+        &mut self,
+        regulator_mode: RegulatorMode,
+    ) -> Result<Status, SxError<T::SpiError, T::PinError>> {
+        let status = self
+            .command(&mut cmd::set_regulator_mode(regulator_mode))
+            .await?;
+        self.config.regulator_mode = regulator_mode;
+        Ok(status)
+    }
+
+    /// Select the mode the modem goes into after TX or RX completes.
+    /// FallbackMode::Fs gives the fastest TX to RX turnaround
+    pub async fn set_rx_tx_fallback_mode(
+        &mut self,
+        fallback_mode: FallbackMode,
+    ) -> Result<Status, SxError<T::SpiError, T::PinError>> {
+        let status = self
+            .command(&mut cmd::set_rx_tx_fallback_mode(fallback_mode))
+            .await?;
+        self.config.fallback_mode = fallback_mode;
+        Ok(status)
+    }
+
+    /// Set the CAD parameters. See SX126x::set_cad_config for the recommended settings
+    pub async fn set_cad_config(
+        &mut self,
+        cad_params: CadParams,
+    ) -> Result<Status, SxError<T::SpiError, T::PinError>> {
+        self.command(&mut cmd::set_cad_params(cad_params)).await
+    }
+
+    /// Set the modem into CAD mode, 0xC5
+    pub async fn set_cad_mode(&mut self) -> Result<Status, SxError<T::SpiError, T::PinError>> {
+        self.command(&mut cmd::operating_mode(OperatingModes::SetCad))
+            .await
+    }
+
+    /// Get the current status of the modem
+    pub async fn get_status(&mut self) -> Result<Status, SxError<T::SpiError, T::PinError>> {
+        let mut result = cmd::get_status();
+        self.transaction(&mut [Operation::TransferInPlace(&mut result)])
+            .await?;
+
+        Ok(result[1].into())
+    }
+
+    pub async fn set_fs(&mut self) -> Result<Status, SxError<T::SpiError, T::PinError>> {
+        self.command(&mut cmd::operating_mode(OperatingModes::SetFs))
+            .await
+    }
+
+    pub async fn get_stats(&mut self) -> Result<Stats, SxError<T::SpiError, T::PinError>> {
+        let mut result = cmd::get_stats();
+        self.transaction(&mut [Operation::TransferInPlace(&mut result)])
+            .await?;
+        self.check(result[1].into())?;
+
+        Ok(TryInto::<[u8; 7]>::try_into(&result[1..]).unwrap().into())
+    }
+
+    /// Calibrate image for the given band. Use CalibImageFreq::from_range to
+    /// cover all frequencies the modem will hop between.
+    /// Fails with SxError::Command outside STDBY_RC, see SX126x::calibrate_image
+    pub async fn calibrate_image(
+        &mut self,
+        freq: CalibImageFreq,
+    ) -> Result<Status, SxError<T::SpiError, T::PinError>> {
+        let status = self.command(&mut cmd::calibrate_image(freq)).await?;
+        if status.is_command_error() || status.chip_mode() != ChipMode::StbyRC {
+            return Err(SxError::Command(status));
+        }
+        self.calib_image = Some(freq);
+        Ok(status)
+    }
+
+    /// Calibrate modem
+    pub async fn calibrate(
+        &mut self,
+        calib_param: CalibParam,
+    ) -> Result<Status, SxError<T::SpiError, T::PinError>> {
+        self.command(&mut cmd::calibrate(calib_param)).await
+    }
+
+    /// Write data into a register
+    pub async fn write_register(
+        &mut self,
+        register: Register,
+        data: &[u8],
+    ) -> Result<Status, SxError<T::SpiError, T::PinError>> {
+        let mut header = cmd::write_register(register);
+        let mut ops = [
+            Operation::TransferInPlace(&mut header),
+            Operation::Write(data),
+        ];
+
+        self.transaction(&mut ops).await?;
+        self.check(header[1].into())
+    }
+
+    /// Read data from a register. Multiple bytes are read
+    /// from consecutive addresses, starting at register
+    pub async fn read_register(
+        &mut self,
+        register: Register,
+        result: &mut [u8],
+    ) -> Result<Status, SxError<T::SpiError, T::PinError>> {
+        debug_assert!(!result.is_empty());
+        // The status byte is clocked out before the register data
+        let mut header = cmd::read_register(register);
+        let mut ops = [
+            Operation::TransferInPlace(&mut header),
+            Operation::Read(result),
+        ];
+
+        self.transaction(&mut ops).await?;
+        self.check(header[1].into())
+    }
+
+    /// Read a single register
+    pub async fn read_reg(&mut self, register: Register) -> Result<u8, SxError<T::SpiError, T::PinError>> {
+        let mut value = [NOP];
+        self.read_register(register, &mut value).await?;
+        Ok(value[0])
+    }
+
+    /// Read a 16-bit value, MSB first, from register and the one after it
+    pub async fn read_reg_u16(
+        &mut self,
+        register: Register,
+    ) -> Result<u16, SxError<T::SpiError, T::PinError>> {
+        let mut value = [NOP; 2];
+        self.read_register(register, &mut value).await?;
+        Ok(u16::from_be_bytes(value))
+    }
+
+    /// Read a 32-bit value, MSB first, from register and the three after it
+    pub async fn read_reg_u32(
+        &mut self,
+        register: Register,
+    ) -> Result<u32, SxError<T::SpiError, T::PinError>> {
+        let mut value = [NOP; 4];
+        self.read_register(register, &mut value).await?;
+        Ok(u32::from_be_bytes(value))
+    }
+
+    /// Read a register, update its value with f and write it back.
+    /// Returns the status of the write, or of the read if that reported
+    /// a command error, along with the value written
+    pub async fn modify_reg(
+        &mut self,
+        register: Register,
+        f: impl FnOnce(u8) -> u8,
+    ) -> Result<(Status, u8), SxError<T::SpiError, T::PinError>> {
+        let mut value = [NOP];
+        let read = self.read_register(register, &mut value).await?;
+        let value = f(value[0]);
+        let write = self.write_register(register, &[value]).await?;
+        Ok((sequence_status(read, write), value))
+    }
+
+    /// Write data into the buffer at the defined offset
+    pub async fn write_buffer(
+        &mut self,
+        offset: u8,
+        data: &[u8],
+    ) -> Result<Status, SxError<T::SpiError, T::PinError>> {
+        let mut header = cmd::write_buffer(offset);
+        let mut ops = [
+            Operation::TransferInPlace(&mut header),
+            Operation::Write(data),
+        ];
+        self.transaction(&mut ops).await?;
+        self.check(header[1].into())
+    }
+
+    /// Read data from the data from the defined offset
+    pub async fn read_buffer(
+        &mut self,
+        offset: u8,
+        result: &mut [u8],
+    ) -> Result<Status, SxError<T::SpiError, T::PinError>> {
+        let mut header = cmd::read_buffer(offset);
+        let mut ops = [
+            Operation::TransferInPlace(&mut header),
+            Operation::Read(result),
+        ];
+        self.transaction(&mut ops).await?;
+        self.check(header[1].into())
+    }
+
+    /// Configure the dio2 pin as RF control switch
+    pub async fn set_dio2_as_rf_switch_ctrl(
+        &mut self,
+        enable: bool,
+    ) -> Result<Status, SxError<T::SpiError, T::PinError>> {
+        self.command(&mut cmd::set_dio2_as_rf_switch_ctrl(enable))
+            .await
+    }
+
+    /// Get the status of the last received LoRa packet, along with the command status
+    pub async fn get_packet_status(
+        &mut self,
+    ) -> Result<(Status, PacketStatus), SxError<T::SpiError, T::PinError>> {
+        let mut header = cmd::get_packet_status();
+        let mut result = [NOP; 3];
+        let mut ops = [
+            Operation::TransferInPlace(&mut header),
+            Operation::Read(&mut result),
+        ];
+        self.transaction(&mut ops).await?;
+
+        Ok((self.check(header[1].into())?, result.into()))
+    }
+
+    /// Get the status of the last received GFSK packet.
+    /// A packet dropped by the address filter is reported through
+    /// GfskRxStatus::address_err
+    pub async fn get_gfsk_packet_status(
+        &mut self,
+    ) -> Result<(Status, GfskPacketStatus), SxError<T::SpiError, T::PinError>> {
+        let mut header = cmd::get_packet_status();
+        let mut result = [NOP; 3];
+        let mut ops = [
+            Operation::TransferInPlace(&mut header),
+            Operation::Read(&mut result),
+        ];
+        self.transaction(&mut ops).await?;
+
+        Ok((self.check(header[1].into())?, result.into()))
+    }
+
+    /// Configure the dio3 pin as TCXO control switch
+    pub async fn set_dio3_as_tcxo_ctrl(
+        &mut self,
+        tcxo_voltage: TcxoVoltage,
+        tcxo_delay: TcxoDelay,
+    ) -> Result<Status, SxError<T::SpiError, T::PinError>> {
+        self.command(&mut cmd::set_dio3_as_tcxo_ctrl(tcxo_voltage, tcxo_delay))
+            .await
+    }
+
+    /// Clear device error register
+    pub async fn clear_device_errors(&mut self) -> Result<Status, SxError<T::SpiError, T::PinError>> {
+        self.command(&mut cmd::clear_device_errors()).await
+    }
+
+    /// Get current device errors
+    pub async fn get_device_errors(&mut self) -> Result<DeviceErrors, SxError<T::SpiError, T::PinError>> {
+        let mut result = cmd::get_device_errors();
+        self.transaction(&mut [Operation::TransferInPlace(&mut result)])
+            .await?;
+        self.check(result[1].into())?;
+        Ok(DeviceErrors::from(u16::from_be_bytes(
+            result[2..].try_into().unwrap(),
+        )))
+    }
+
+    /// Reset the device py pulling nrst low for a while
+    pub async fn reset(&mut self) -> Result<(), SxError<T::SpiError, T::PinError>> {
+        self.calib_image = None;
+        self.bus.reset().await?;
+        self.test_tx_pending = false;
+        Ok(())
+    }
+
+    /// Enable antenna
+    pub fn set_ant_enabled(&mut self, enabled: bool) -> Result<(), T::PinError> {
+        if enabled {
+            self.ant_pin.set_high()
+        } else {
+            self.ant_pin.set_low()
+        }
+    }
+
+    /// Configure IRQ
+    pub async fn set_dio_irq_params(
+        &mut self,
+        irq_mask: IrqMask,
+        dio1_mask: IrqMask,
+        dio2_mask: IrqMask,
+        dio3_mask: IrqMask,
+    ) -> Result<Status, SxError<T::SpiError, T::PinError>> {
+        let status = self
+            .command(&mut cmd::set_dio_irq_params(
+                irq_mask, dio1_mask, dio2_mask, dio3_mask,
+            ))
+            .await?;
+        self.config.irq_mask = irq_mask;
+        self.config.dio1_irq_mask = dio1_mask;
+        self.config.dio2_irq_mask = dio2_mask;
+        self.config.dio3_irq_mask = dio3_mask;
+        Ok(status)
+    }
+
+    /// Get the current IRQ status, along with the command status
+    pub async fn get_irq_status(
+        &mut self,
+    ) -> Result<(Status, IrqStatus), SxError<T::SpiError, T::PinError>> {
+        let mut irq_status = [NOP, NOP];
+        let mut header = cmd::get_irq_status();
+        let mut ops = [
+            Operation::TransferInPlace(&mut header),
+            Operation::Read(&mut irq_status),
+        ];
+        self.transaction(&mut ops).await?;
+        Ok((
+            self.check(header[1].into())?,
+            u16::from_be_bytes(irq_status).into(),
+        ))
+    }
+
+    /// Clear the IRQ status
+    pub async fn clear_irq_status(
+        &mut self,
+        mask: IrqMask,
+    ) -> Result<Status, SxError<T::SpiError, T::PinError>> {
+        self.command(&mut cmd::clear_irq_status(mask)).await
+    }
+
+    /// Put the device in TX mode. It will start sending the data written in the buffer,
+    /// starting at the configured offset
+    pub async fn set_tx(
+        &mut self,
+        timeout: RxTxTimeout,
+    ) -> Result<Status, SxError<T::SpiError, T::PinError>> {
+        self.command(&mut cmd::set_tx(timeout)).await
+    }
+
+    pub async fn set_rx(
+        &mut self,
+        timeout: RxTxTimeout,
+    ) -> Result<Status, SxError<T::SpiError, T::PinError>> {
+        self.command(&mut cmd::set_rx(timeout)).await
+    }
+
+    /// Put the device in RX mode for a window of the given number of symbols, calculated
+    /// from the current LoRa modulation parameters
+    pub async fn set_rx_symbols(
+        &mut self,
+        symbols: u16,
+    ) -> Result<Status, SxError<T::SpiError, T::PinError>> {
+        if self.config.mod_params.lora().is_none() {
+            return Err(SxError::InvalidConfig(ConfigError::RxWindowRequiresLoRa));
+        }
+        if symbols == 0 {
+            return Err(SxError::InvalidConfig(ConfigError::RxWindowEmpty));
+        }
+        let timeout = RxTxTimeout::from_lora_symbols(&self.config.mod_params, symbols).ok_or(
+            SxError::InvalidConfig(ConfigError::RxWindowTooLong(symbols)),
+        )?;
+        self.set_rx(timeout).await
+    }
+
+    /// Select the event that stops the RX timeout timer: preamble detection if enabled,
+    /// otherwise sync word (GFSK) or header (LoRa) detection
+    pub async fn stop_timer_on_preamble(
+        &mut self,
+        enable: bool,
+    ) -> Result<Status, SxError<T::SpiError, T::PinError>> {
+        self.command(&mut cmd::stop_timer_on_preamble(enable)).await
+    }
+
+    /// Set the number of symbols the modem waits for to validate a reception.
+    /// Fails with SxError::InvalidConfig above LORA_SYMB_NUM_TIMEOUT_MAX symbols
+    pub async fn set_lora_symb_num_timeout(
+        &mut self,
+        symb_num: u8,
+    ) -> Result<Status, SxError<T::SpiError, T::PinError>> {
+        let timeout = LoRaSymbNumTimeout::new(symb_num).ok_or(SxError::InvalidConfig(
+            ConfigError::SymbNumTimeoutOutOfRange(symb_num),
+        ))?;
+        let status = self
+            .command(&mut cmd::set_lora_symb_num_timeout(timeout))
+            .await?;
+        if symb_num == 0 {
+            return Ok(status);
+        }
+        let then = self
+            .write_register(Register::LoRaSynchTimeout, &[timeout.register_value()])
+            .await?;
+        Ok(sequence_status(status, then))
+    }
+
+    /// Start a test transmission with the given opcode.
+    /// Returns the mode the modem was in before, for TestTx to return to
+    pub(super) async fn start_test_tx(
+        &mut self,
+        opcode: OperatingModes,
+    ) -> Result<ChipMode, SxError<T::SpiError, T::PinError>> {
+        let previous_mode = self.get_status().await?.chip_mode();
+        self.command(&mut cmd::operating_mode(opcode)).await?;
+        Ok(previous_mode)
+    }
+
+    /// Record that a test transmission was dropped without being stopped,
+    /// so the next command puts the modem in STDBY_RC first
+    pub(super) fn abandon_test_tx(&mut self) {
+        self.test_tx_pending = true;
+    }
+
+    /// Put the device in RX duty cycle (listen) mode, see SX126x::set_rx_duty_cycle
+    pub async fn set_rx_duty_cycle(
+        &mut self,
+        rx_period: RxTxTimeout,
+        sleep_period: RxTxTimeout,
+    ) -> Result<Status, SxError<T::SpiError, T::PinError>> {
+        self.command(&mut cmd::set_rx_duty_cycle(rx_period, sleep_period))
+            .await
+    }
+
+    /// Set packet parameters
+    pub async fn set_packet_params(
+        &mut self,
+        params: &PacketParams,
+    ) -> Result<Status, SxError<T::SpiError, T::PinError>> {
+        let status = self.command(&mut cmd::set_packet_params(params)).await?;
+        self.config.packet_params = Some(*params);
+        Ok(status)
+    }
+
+    /// Set modulation parameters, see SX126x::set_mod_params
+    pub async fn set_mod_params(
+        &mut self,
+        params: &ModParams,
+    ) -> Result<Status, SxError<T::SpiError, T::PinError>> {
+        if params
+            .lora()
+            .is_some_and(LoraModParams::low_dr_opt_rejected)
+        {
+            return Err(SxError::InvalidConfig(ConfigError::LowDataRateOptConflict));
+        }
+        let status = self
+            .command(&mut cmd::set_mod_params(params, self.config.xtal_freq))
+            .await?;
+        self.config.mod_params = *params;
+        Ok(status)
+    }
+
+    /// Set TX parameters
+    pub async fn set_tx_params(
+        &mut self,
+        params: &TxParams,
+    ) -> Result<Status, SxError<T::SpiError, T::PinError>> {
+        let status = self.command(&mut cmd::set_tx_params(params)).await?;
+        self.config.tx_params = *params;
+        Ok(status)
+    }
+
+    /// Set RF frequency. The frequency is converted to PLL steps
+    /// using the XTAL frequency from the Config.
+    /// If the frequency lies outside the band of the last image calibration,
+    /// the image is calibrated again in STDBY_RC, see SX126x::set_rf_frequency
+    pub async fn set_rf_frequency(
+        &mut self,
+        rf_frequency: Frequency,
+    ) -> Result<Status, SxError<T::SpiError, T::PinError>> {
+        let rf_freq = rf_frequency.to_pll_steps(self.config.xtal_freq);
+        let status = self.command(&mut cmd::set_rf_frequency(rf_freq)).await?;
+        self.config.rf_frequency = rf_frequency;
+
+        if self
+            .calib_image
+            .is_some_and(|calib_image| !calib_image.contains(rf_frequency))
+        {
+            let previous_mode = status.chip_mode();
+            if matches!(previous_mode, ChipMode::StbyXOSC | ChipMode::FS) {
+                self.set_standby(StandbyConfig::StbyRc).await?;
+            }
+            let then = self
+                .calibrate_image(CalibImageFreq::from_rf_frequency(rf_frequency))
+                .await?;
+            match previous_mode {
+                ChipMode::StbyXOSC => {
+                    self.set_standby(StandbyConfig::StbyXOSC).await?;
+                }
+                ChipMode::FS => {
+                    self.set_fs().await?;
+                }
+                _ => {}
+            }
+            return Ok(sequence_status(status, then));
+        }
+        Ok(status)
+    }
+
+    /// Set Power Amplifier configuration
+    pub async fn set_pa_config(
+        &mut self,
+        pa_config: &PaConfig,
+    ) -> Result<Status, SxError<T::SpiError, T::PinError>> {
+        let status = self.command(&mut cmd::set_pa_config(pa_config)).await?;
+
+        // Now we need to apply or remove the clamping fix for the PA
+        let apply_fix = pa_config.pa_clamp_fix_enabled();
+        let (then, _) = self
+            .modify_reg(Register::TxClampConfig, |clamp_config| {
+                cmd::tx_clamp_config(clamp_config, apply_fix)
+            })
+            .await?;
+        self.config.pa_config = pa_config.clone();
+        Ok(sequence_status(status, then))
+    }
+
+    /// Configure the base addresses in the buffer
+    pub async fn set_buffer_base_address(
+        &mut self,
+        tx_base_addr: u8,
+        rx_base_addr: u8,
+    ) -> Result<Status, SxError<T::SpiError, T::PinError>> {
+        self.command(&mut cmd::set_buffer_base_address(
+            tx_base_addr,
+            rx_base_addr,
+        ))
+        .await
+    }
+
+    pub async fn fix_sensitivity(&mut self) -> Result<Status, SxError<T::SpiError, T::PinError>> {
+        // Apply the sensitivity fix for LoRa with a 500 kHz bandwidth, remove it otherwise
+        let packet_type = self.get_packet_type().await?;
+        let mod_params = self.config.mod_params;
+        let (status, _) = self
+            .modify_reg(Register::SensitivityConfig, |sensitivity_config| {
+                cmd::sensitivity_config(sensitivity_config, packet_type, &mod_params)
+            })
+            .await?;
+        Ok(status)
+    }
+
+    /// Get Rx buffer status, containing the length of the last received packet
+    /// and the address of the first byte received.
+    pub async fn get_rx_buffer_status(
+        &mut self,
+    ) -> Result<RxBufferStatus, SxError<T::SpiError, T::PinError>> {
+        let mut result = cmd::get_rx_buffer_status();
+        self.transaction(&mut [Operation::TransferInPlace(&mut result)])
+            .await?;
+        self.check(result[1].into())?;
+        Ok(TryInto::<[u8; 2]>::try_into(&result[2..]).unwrap().into())
+    }
+
+    /// Write data into the buffer, put the modem in TX mode and wait on DIO1 with W
+    /// until the packet is sent or the timeout elapsed. Strategies that keep time give up
+    /// 100 ms after the timeout, or after the time on air of the packet if no timeout is set
+    pub(super) async fn transmit<W: Dio1Wait<T, TDIO1>>(
+        &mut self,
+        data: &[u8],
+        timeout: RxTxTimeout,
+        params: PacketParams,
+    ) -> Result<TxOutcome, SxError<T::SpiError, T::PinError>> {
+        let deadline_us = timeout
+            .to_us()
+            .or_else(|| packet_time_us(&self.config.mod_params, &params, data.len()))
+            .map(|time_us| time_us.saturating_add(DIO1_MARGIN_US));
+
+        self.run_flow(flow::prepare_tx(data, &params)).await?;
+
+        // Set tx mode
+        let status = self.set_tx(timeout).await?;
+        if status.is_command_error() {
+            return Ok(TxOutcome::Failed(status));
+        }
+        // Wait for busy line to go low
+        self.bus.wait_on_busy().await?;
+
+        // Wait on dio1 going high, until the packet is sent or the timeout elapsed
+        let irq_status = loop {
+            W::wait_on_dio1(&mut self.bus, &mut self.dio1_pin, deadline_us).await?;
+            if let Some(irq_status) = self.take_irq_status(tx_finished).await? {
+                break irq_status;
+            }
+        };
+        Ok(irq_status.into())
+    }
+
+    /// Put the modem in RX mode, wait on DIO1 with W until a packet is received or the
+    /// timeout elapsed, and copy the payload into buf. The timer of the modem may stop once
+    /// a preamble is detected, so strategies that keep time only give up after the timeout,
+    /// the time on air of the longest packet and 100 ms together
+    pub(super) async fn receive<W: Dio1Wait<T, TDIO1>>(
+        &mut self,
+        buf: &mut [u8],
+        timeout: RxTxTimeout,
+    ) -> Result<RxPacket, SxError<T::SpiError, T::PinError>> {
+        let deadline_us = timeout.to_us().map(|timeout_us| {
+            let packet_us = self
+                .config
+                .packet_params
+                .as_ref()
+                .and_then(|params| packet_time_us(&self.config.mod_params, params, 255))
+                .unwrap_or(0);
+            timeout_us
+                .saturating_add(packet_us)
+                .saturating_add(DIO1_MARGIN_US)
+        });
+
+        self.run_flow(flow::prepare_rx(&self.config)).await?;
+
+        // Set rx mode
+        self.set_rx(timeout).await?;
+        self.bus.wait_on_busy().await?;
+
+        let irq_status = self.wait_rx_finished::<W>(deadline_us).await;
+        // Also restore the IRQ masks when waiting failed, but report the first error
+        let restored = self.run_flow(flow::end_rx(&self.config)).await;
+        let irq_status = irq_status?;
+        restored?;
+        self.finish_rx(irq_status, buf).await
+    }
+
+    /// Wait on DIO1 with W until the IRQ status shows that the reception ended
+    async fn wait_rx_finished<W: Dio1Wait<T, TDIO1>>(
+        &mut self,
+        deadline_us: Option<u32>,
+    ) -> Result<IrqStatus, SxError<T::SpiError, T::PinError>> {
+        loop {
+            W::wait_on_dio1(&mut self.bus, &mut self.dio1_pin, deadline_us).await?;
+            if let Some(irq_status) = self.take_irq_status(rx_finished).await? {
+                return Ok(irq_status);
+            }
+        }
+    }
+
+    /// Read and clear the IRQ status after DIO1 went high. Returns the status if it
+    /// contains an IRQ for which finished returns true. Intermediate IRQs, like
+    /// PreambleDetected, are cleared so DIO1 goes low again, and None is returned
+    async fn take_irq_status(
+        &mut self,
+        finished: fn(IrqStatus) -> bool,
+    ) -> Result<Option<IrqStatus>, SxError<T::SpiError, T::PinError>> {
+        let (_, irq_status) = self.get_irq_status().await?;
+        self.clear_irq_status(irq_status.into()).await?;
+        Ok(finished(irq_status).then_some(irq_status))
+    }
+
+    /// Check the outcome of a reception, and copy the received payload into buf
+    async fn finish_rx(
+        &mut self,
+        irq_status: IrqStatus,
+        buf: &mut [u8],
+    ) -> Result<RxPacket, SxError<T::SpiError, T::PinError>> {
+        let gfsk_status = match self.config.packet_type {
+            PacketType::GFSK => Some(self.get_gfsk_packet_status().await?.1),
+            PacketType::LoRa => None,
+        };
+
+        check_rx(irq_status, gfsk_status.as_ref())?;
+
+        let rx_buffer_status = self.get_rx_buffer_status().await?;
+        let len = rx_buffer_status.payload_length_rx();
+        let payload = buf
+            .get_mut(..len as usize)
+            .ok_or(RxError::BufferTooSmall(len))?;
+        self.read_buffer(rx_buffer_status.rx_start_buffer_pointer(), payload)
+            .await?;
+
+        let packet = match gfsk_status {
+            Some(status) => gfsk_rx_packet(len, &status),
+            None => lora_rx_packet(len, &self.get_packet_status().await?.1),
+        };
+        Ok(packet)
+    }
+
+    /// Read a 32-bit random number from the modem. The modem is put in continuous RX
+    /// with all IRQs disabled while the number is read, and is left in STDBY_RC with
+    /// the IRQ masks of the stored Config afterwards
+    pub async fn random_u32(&mut self) -> Result<u32, SxError<T::SpiError, T::PinError>> {
+        self.run_flow(flow::start_random()).await?;
+        let random = self.read_reg_u32(Register::RandomNumberGen0).await?;
+
+        self.run_flow(flow::stop_random(&self.config)).await?;
+        Ok(random)
+    }
+}
+
+/// Time on air of a packet of len bytes in μs, None if the packet params do not match
+/// the modulation. For GFSK the length byte, address and a 2 byte CRC are always counted
+fn packet_time_us(mod_params: &ModParams, params: &PacketParams, len: usize) -> Option<u32> {
+    let len = len.min(u8::MAX as usize) as u8;
+    let time_us = match (mod_params, params) {
+        (ModParams::LoRa(_), PacketParams::LoRa(lora)) => time_on_air(mod_params, lora, len)?,
+        (ModParams::Gfsk(gfsk_mod), PacketParams::Gfsk(gfsk)) => {
+            let bits = gfsk.preamble_len as u64 + gfsk.sync_word_len as u64 + 8 * (len as u64 + 4);
+            (bits * 1_000_000).div_ceil(gfsk_mod.bitrate().max(1) as u64)
+        }
+        _ => return None,
+    };
+    Some(time_us.min(u32::MAX as u64) as u32)
+}
@@ -1,4 +1,6 @@
-use core::fmt::{self, Debug};
+use core::fmt::{self, Debug, Display};
+
+use crate::op::{ConfigError, DecodeError, DeviceErrors, Status};
 
 pub enum SpiError<TSPIERR> {
     Write(TSPIERR),
@@ -14,6 +16,17 @@ impl<TSPIERR: Debug> Debug for SpiError<TSPIERR> {
     }
 }
 
+impl<TSPIERR: Debug> Display for SpiError<TSPIERR> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Write(err) => write!(f, "SPI write failed: {:?}", err),
+            Self::Transfer(err) => write!(f, "SPI transfer failed: {:?}", err),
+        }
+    }
+}
+
+impl<TSPIERR: Debug> core::error::Error for SpiError<TSPIERR> {}
+
 pub enum PinError<TPINERR> {
     Output(TPINERR),
     Input(TPINERR),
@@ -28,9 +41,65 @@ impl<TPINERR: Debug> Debug for PinError<TPINERR> {
     }
 }
 
+impl<TPINERR: Debug> Display for PinError<TPINERR> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Output(err) => write!(f, "setting output pin failed: {:?}", err),
+            Self::Input(err) => write!(f, "reading input pin failed: {:?}", err),
+        }
+    }
+}
+
+impl<TPINERR: Debug> core::error::Error for PinError<TPINERR> {}
+
+/// Reasons a reception did not produce a packet
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum RxError {
+    /// No packet was received before the RX timeout
+    Timeout,
+    /// A packet was received, but its payload CRC was invalid
+    CrcErr,
+    /// A LoRa header was received, but its CRC was invalid
+    HeaderError,
+    /// A GFSK packet was dropped by the node or broadcast address filter
+    AddressFiltered,
+    /// The received packet, of the contained length, does not fit in the buffer
+    BufferTooSmall(u8),
+}
+
+impl Display for RxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Timeout => write!(f, "no packet received before the RX timeout"),
+            Self::CrcErr => write!(f, "payload CRC error"),
+            Self::HeaderError => write!(f, "LoRa header CRC error"),
+            Self::AddressFiltered => write!(f, "packet dropped by the GFSK address filter"),
+            Self::BufferTooSmall(len) => {
+                write!(f, "received packet of {} bytes does not fit in the buffer", len)
+            }
+        }
+    }
+}
+
+impl core::error::Error for RxError {}
+
 pub enum SxError<TSPIERR, TPINERR> {
     Spi(SpiError<TSPIERR>),
     Pin(PinError<TPINERR>),
+    /// A parameter was passed that the modem does not accept
+    InvalidConfig(ConfigError),
+    /// The modem could not process or execute a command, see the contained status
+    Command(Status),
+    /// The modem reported errors, as read with SX126x::get_device_errors
+    Device(DeviceErrors),
+    /// A response from the modem could not be decoded
+    Decode(DecodeError),
+    /// A reception failed
+    Rx(RxError),
+    /// A pin did not reach the expected level in time
+    Timeout,
+    /// A command was issued while the modem is asleep, wake it up first
+    Asleep,
 }
 
 impl<TSPIERR: Debug, TPINERR: Debug> Debug for SxError<TSPIERR, TPINERR> {
@@ -38,6 +107,40 @@ impl<TSPIERR: Debug, TPINERR: Debug> Debug for SxError<TSPIERR, TPINERR> {
         match self {
             Self::Spi(err) => write!(f, "Spi({:?})", err),
             Self::Pin(err) => write!(f, "Pin({:?})", err),
+            Self::InvalidConfig(err) => write!(f, "InvalidConfig({:?})", err),
+            Self::Command(status) => write!(f, "Command({:?})", status),
+            Self::Device(errors) => write!(f, "Device({:?})", errors),
+            Self::Decode(err) => write!(f, "Decode({:?})", err),
+            Self::Rx(err) => write!(f, "Rx({:?})", err),
+            Self::Timeout => write!(f, "Timeout"),
+            Self::Asleep => write!(f, "Asleep"),
+        }
+    }
+}
+
+impl<TSPIERR: Debug, TPINERR: Debug> Display for SxError<TSPIERR, TPINERR> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Spi(err) => Display::fmt(err, f),
+            Self::Pin(err) => Display::fmt(err, f),
+            Self::InvalidConfig(err) => write!(f, "invalid configuration: {}", err),
+            Self::Command(status) => write!(f, "command failed: {:?}", status.command_status()),
+            Self::Device(errors) => write!(f, "device errors: {:?}", errors),
+            Self::Decode(err) => write!(f, "invalid response: {}", err),
+            Self::Rx(err) => write!(f, "reception failed: {}", err),
+            Self::Timeout => write!(f, "timed out waiting on the modem"),
+            Self::Asleep => write!(f, "the modem is asleep"),
+        }
+    }
+}
+
+impl<TSPIERR: Debug, TPINERR: Debug> core::error::Error for SxError<TSPIERR, TPINERR> {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Self::InvalidConfig(err) => Some(err),
+            Self::Decode(err) => Some(err),
+            Self::Rx(err) => Some(err),
+            _ => None,
         }
     }
 }
@@ -53,3 +156,27 @@ impl<TSPIERR, TPINERR> From<PinError<TPINERR>> for SxError<TSPIERR, TPINERR> {
         SxError::Pin(spi_err)
     }
 }
+
+impl<TSPIERR, TPINERR> From<RxError> for SxError<TSPIERR, TPINERR> {
+    fn from(rx_err: RxError) -> Self {
+        SxError::Rx(rx_err)
+    }
+}
+
+impl<TSPIERR, TPINERR> From<ConfigError> for SxError<TSPIERR, TPINERR> {
+    fn from(config_err: ConfigError) -> Self {
+        SxError::InvalidConfig(config_err)
+    }
+}
+
+impl<TSPIERR, TPINERR> From<DeviceErrors> for SxError<TSPIERR, TPINERR> {
+    fn from(errors: DeviceErrors) -> Self {
+        SxError::Device(errors)
+    }
+}
+
+impl<TSPIERR, TPINERR> From<DecodeError> for SxError<TSPIERR, TPINERR> {
+    fn from(decode_err: DecodeError) -> Self {
+        SxError::Decode(decode_err)
+    }
+}
@@ -0,0 +1,193 @@
+use crate::conf::Config;
+use crate::op::*;
+
+/// A single command of a multi-command flow. The modem is busy after each
+/// step, so the driver has to wait on BUSY before running the next one
+pub(super) enum Step<'a> {
+    Standby(StandbyConfig),
+    RegulatorMode(RegulatorMode),
+    PacketType(PacketType),
+    RfFrequency(Frequency),
+    Dio3AsTcxoCtrl(TcxoVoltage, TcxoDelay),
+    Calibrate(CalibParam),
+    CalibrateImage(CalibImageFreq),
+    PaConfig(&'a PaConfig),
+    TxParams(&'a TxParams),
+    BufferBaseAddress(u8, u8),
+    FallbackMode(FallbackMode),
+    ModParams(&'a ModParams),
+    PacketParams(&'a PacketParams),
+    GfskNodeAddress(u8),
+    GfskBroadcastAddress(u8),
+    GfskSyncWord(GfskSyncWord),
+    GfskCrc(GfskCrc),
+    WhiteningSeed(u16),
+    DioIrqParams(IrqMask, IrqMask, IrqMask, IrqMask),
+    Dio2AsRfSwitchCtrl(bool),
+    SyncWord(u16),
+    WriteBuffer(u8, &'a [u8]),
+    ClearIrqStatus(IrqMask),
+    FixSensitivity,
+    Rx(RxTxTimeout),
+    Fs,
+    ClearDeviceErrors,
+    /// Fail with SxError::Device if the modem reports any error
+    CheckDeviceErrors,
+}
+
+/// The steps that apply conf to the modem. Used by init and to restore
+/// the configuration after a cold start
+pub(super) fn configure(conf: &Config) -> impl Iterator<Item = Step<'_>> {
+    let addr_filter = conf
+        .packet_params
+        .as_ref()
+        .and_then(PacketParams::gfsk)
+        .is_some_and(|gfsk| gfsk.addr_comp != GfskAddrComp::Off);
+
+    [
+        // 1. If not in STDBY_RC mode, then go to this mode with the command SetStandby(...)
+        Some(Step::Standby(StandbyConfig::StbyRc)),
+        // Select LDO or DC-DC
+        Some(Step::RegulatorMode(conf.regulator_mode)),
+        // 2. Define the protocol (LoRa® or FSK) with the command SetPacketType(...)
+        Some(Step::PacketType(conf.packet_type)),
+        // 3. Define the RF frequency with the command SetRfFrequency(...)
+        Some(Step::RfFrequency(conf.rf_frequency)),
+        conf.tcxo_opts
+            .map(|(tcxo_voltage, tcxo_delay)| Step::Dio3AsTcxoCtrl(tcxo_voltage, tcxo_delay)),
+        // The modem flags XoscStartErr when it starts without a configured TCXO
+        Some(Step::ClearDeviceErrors),
+        // Calibrate
+        Some(Step::Calibrate(conf.calib_param)),
+        Some(Step::CalibrateImage(CalibImageFreq::from_rf_frequency(
+            conf.rf_frequency,
+        ))),
+        Some(Step::CheckDeviceErrors),
+        // 4. Define the Power Amplifier configuration with the command SetPaConfig(...)
+        Some(Step::PaConfig(&conf.pa_config)),
+        // 5. Define output power and ramping time with the command SetTxParams(...)
+        Some(Step::TxParams(&conf.tx_params)),
+        // 6. Define where the data payload will be stored with the command SetBufferBaseAddress(...)
+        Some(Step::BufferBaseAddress(0x00, 0x00)),
+        Some(Step::FallbackMode(conf.fallback_mode)),
+        // 7. Send the payload to the data buffer with the command WriteBuffer(...)
+        // This is done later in SX126x::write_bytes
+
+        // 8. Define the modulation parameter according to the chosen protocol with the command SetModulationParams(...)
+        Some(Step::ModParams(&conf.mod_params)),
+        // 9. Define the frame format to be used with the command SetPacketParams(...)
+        conf.packet_params.as_ref().map(Step::PacketParams),
+        // Program the addresses the GFSK address filter compares against
+        addr_filter.then_some(Step::GfskNodeAddress(conf.gfsk_address.node)),
+        addr_filter.then_some(Step::GfskBroadcastAddress(conf.gfsk_address.broadcast)),
+        conf.gfsk_sync_word.map(Step::GfskSyncWord),
+        conf.gfsk_crc.map(Step::GfskCrc),
+        conf.whitening_seed.map(Step::WhiteningSeed),
+        // 10. Configure DIO and IRQ: use the command SetDioIrqParams(...) to select TxDone IRQ and map this IRQ to a DIO (DIO1,
+        // DIO2 or DIO3)
+        Some(Step::DioIrqParams(
+            conf.irq_mask,
+            conf.dio1_irq_mask,
+            conf.dio2_irq_mask,
+            conf.dio3_irq_mask,
+        )),
+        Some(Step::Dio2AsRfSwitchCtrl(true)),
+        // 11. Define Sync Word value: use the command WriteReg(...) to write the value of the register via direct register access
+        Some(Step::SyncWord(conf.sync_word)),
+        // The rest of the steps are done by the user
+    ]
+    .into_iter()
+    .flatten()
+}
+
+/// The steps that write data into the buffer and prepare the modem for SetTx
+pub(super) fn prepare_tx<'a>(
+    data: &'a [u8],
+    params: &'a PacketParams,
+) -> impl Iterator<Item = Step<'a>> {
+    [
+        Step::PacketParams(params),
+        // Set Buffer Pointers
+        Step::BufferBaseAddress(0x00, 0x00),
+        // Write data to buffer
+        Step::WriteBuffer(0x00, data),
+        // Clear IRQ
+        Step::ClearIrqStatus(IrqMask::all()),
+        // Fix Sensitivity
+        Step::FixSensitivity,
+    ]
+    .into_iter()
+}
+
+/// The steps that prepare the modem for SetRx, receiving into the start of the buffer
+pub(super) fn prepare_rx(conf: &Config) -> impl Iterator<Item = Step<'static>> {
+    [
+        // Clear IRQ
+        Step::ClearIrqStatus(IrqMask::all()),
+        // A LoRa header error raises HeaderError without RxDone. Route it to DIO1 so
+        // the reception ends, as Semtech's LoRaMac-node driver ends it on IRQ_HEADER_ERROR
+        // (radio.c, RadioIrqProcess). end_rx restores the masks of the Config afterwards
+        Step::DioIrqParams(
+            conf.irq_mask.combine(IrqMaskBit::HeaderError),
+            conf.dio1_irq_mask.combine(IrqMaskBit::HeaderError),
+            conf.dio2_irq_mask,
+            conf.dio3_irq_mask,
+        ),
+        // Set Buffer Pointers
+        Step::BufferBaseAddress(0x00, 0x00),
+    ]
+    .into_iter()
+}
+
+/// The steps that restore the IRQ masks of conf once a reception ended, see prepare_rx
+pub(super) fn end_rx(conf: &Config) -> impl Iterator<Item = Step<'static>> {
+    [irq_params(conf)].into_iter()
+}
+
+/// The steps that disable all IRQs and put the modem in continuous RX.
+/// The RandomNumberGen registers only hold random values in this state
+pub(super) fn start_random() -> impl Iterator<Item = Step<'static>> {
+    [
+        Step::DioIrqParams(
+            IrqMask::none(),
+            IrqMask::none(),
+            IrqMask::none(),
+            IrqMask::none(),
+        ),
+        Step::Rx(RxTxTimeout::continuous_rx()),
+    ]
+    .into_iter()
+}
+
+/// The steps that put the modem back in STDBY_RC with the IRQ masks of conf
+/// after a random number was read
+pub(super) fn stop_random(conf: &Config) -> impl Iterator<Item = Step<'static>> {
+    [
+        Step::Standby(StandbyConfig::StbyRc),
+        irq_params(conf),
+    ]
+    .into_iter()
+}
+
+/// The steps that end a test transmission: STDBY_RC, and from there back into
+/// STDBY_XOSC or FS if the modem was in one of those modes before the test
+pub(super) fn stop_test_tx(previous_mode: ChipMode) -> impl Iterator<Item = Step<'static>> {
+    let restore = match previous_mode {
+        ChipMode::StbyXOSC => Some(Step::Standby(StandbyConfig::StbyXOSC)),
+        ChipMode::FS => Some(Step::Fs),
+        _ => None,
+    };
+    [Some(Step::Standby(StandbyConfig::StbyRc)), restore]
+        .into_iter()
+        .flatten()
+}
+
+/// SetDioIrqParams with the masks of conf
+fn irq_params(conf: &Config) -> Step<'static> {
+    Step::DioIrqParams(
+        conf.irq_mask,
+        conf.dio1_irq_mask,
+        conf.dio2_irq_mask,
+        conf.dio3_irq_mask,
+    )
+}
@@ -1,188 +1,151 @@
 #![allow(async_fn_in_trait)]
 
+mod async_spi;
+mod blocking;
+mod cmd;
+mod driver;
+mod flow;
 pub(crate) mod err;
+mod rng;
+mod test_tx;
+mod transport;
 pub mod wait;
 
-use core::convert::TryInto;
-use embedded_hal::digital::OutputPin;
-use embedded_hal::spi::Operation;
+use core::future::Future;
+use core::pin::pin;
+use core::task::{Context, Poll, Waker};
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::{InputPin, OutputPin};
 use embedded_hal::spi::SpiDevice;
-use err::SpiError;
-
 
 use crate::conf::Config;
 use crate::op::*;
 use crate::reg::*;
 use crate::sx::wait::*;
 
+use self::driver::AwaitDio1;
+
 // use err::OutputPinError;
 
-use self::err::{PinError, SxError};
+pub use self::async_spi::SX126xAsync;
+pub use self::driver::SxDriver;
+pub use self::err::{PinError, RxError, SpiError, SxError};
+pub use self::rng::{SxRng, RNG_ERROR_CODE};
+pub use self::test_tx::TestTx;
+pub use self::transport::{AsyncTransport, BlockingTransport, Transport};
 
 type Pins<TNRST, TBUSY, TANT, TDIO1> = (TNRST, TBUSY, TANT, TDIO1);
 
 const NOP: u8 = 0x00;
 
-/// Calculates the rf_freq value that should be passed to SX126x::set_rf_frequency
-/// based on the desired RF frequency and the XTAL frequency.
+/// Maximum time the modem may keep the BUSY line high, in μs.
+/// The longest operation, a full calibration, takes about 3.5 ms.
+const BUSY_TIMEOUT_US: u32 = 100_000;
+
+/// Interval at which the BUSY and DIO1 lines are polled, in μs
+const POLL_INTERVAL_US: u32 = 10;
+
+/// Calculates the PLL steps for the desired RF frequency and the XTAL frequency.
 ///
 /// Example calculation for 868MHz:
 /// 13.4.1.: RFfrequecy = (RFfreq * Fxtal) / 2^25 = 868M
 /// -> RFfreq =
 /// -> RFfrequecy ~ ((RFfreq >> 12) * (Fxtal >> 12)) >> 1
+#[deprecated(note = "Rounds through f32, use Frequency::to_pll_steps instead")]
 pub fn calc_rf_freq(rf_frequency: f32, f_xtal: f32) -> u32 {
     (rf_frequency * (33554432. / f_xtal)) as u32
 }
 
 /// Wrapper around a Semtech SX1261/62 LoRa modem
-pub struct SX126x<TSPI: SpiDevice, TNRST, TBUSY, TANT, TDIO1> {
-    spi: TSPI,
-    nrst_pin: TNRST,
-    busy_pin: TBUSY,
-    ant_pin: TANT,
-    dio1_pin: TDIO1,
-    config: Config,
+pub struct SX126x<TSPI: SpiDevice, TNRST, TBUSY, TANT, TDIO1, TDELAY> {
+    driver: SxDriver<BlockingTransport<TSPI, TNRST, TBUSY, TDELAY>, TANT, TDIO1>,
 }
 
-impl<TSPI, TNRST, TBUSY, TANT, TDIO1, TSPIERR, TPINERR> SX126x<TSPI, TNRST, TBUSY, TANT, TDIO1>
+impl<TSPI, TNRST, TBUSY, TANT, TDIO1, TDELAY, TSPIERR, TPINERR> SX126x<TSPI, TNRST, TBUSY, TANT, TDIO1, TDELAY>
 where
     TPINERR: core::fmt::Debug,
     TSPI: SpiDevice<Error = TSPIERR>,
     TNRST: OutputPin<Error = TPINERR>,
-    TBUSY: AnyWait<Error = TPINERR>,
+    TBUSY: InputPin<Error = TPINERR>,
     TANT: OutputPin<Error = TPINERR>,
-    TDIO1: AnyWait<Error = TPINERR>,
+    TDELAY: DelayNs,
 {
-    // Create a new SX126x
-    pub fn new(spi: TSPI, pins: Pins<TNRST, TBUSY, TANT, TDIO1>) -> Self {
+    // Create a new SX126x. The delay is used to poll the BUSY and DIO1 pins
+    pub fn new(spi: TSPI, pins: Pins<TNRST, TBUSY, TANT, TDIO1>, delay: TDELAY) -> Self {
         let (nrst_pin, busy_pin, ant_pin, dio1_pin) = pins;
+        let bus = BlockingTransport::new(spi, nrst_pin, busy_pin, delay);
         Self {
-            spi,
-            nrst_pin,
-            busy_pin,
-            ant_pin,
-            dio1_pin,
-            config: Config::default(),
+            driver: SxDriver::with_transport(bus, ant_pin, dio1_pin),
         }
     }
 
-    // Initialize and configure the SX126x using the provided Config
-    pub async fn init_async(&mut self, conf: Config) -> Result<(), SxError<TSPIERR, TPINERR>> {
-
-        // Reset the sx
-        self.reset()?;
-        self.wait_on_busy_async().await?;
-
-        // 1. If not in STDBY_RC mode, then go to this mode with the command SetStandby(...)
-        self.set_standby(crate::op::StandbyConfig::StbyRc)?;
-        self.wait_on_busy_async().await?;
-
-        // 2. Define the protocol (LoRa® or FSK) with the command SetPacketType(...)
-        self.set_packet_type(conf.packet_type)?;
-        self.wait_on_busy_async().await?;
-
-        // 3. Define the RF frequency with the command SetRfFrequency(...)
-        self.set_rf_frequency(conf.rf_freq)?;
-        self.wait_on_busy_async().await?;
-
-        if let Some((tcxo_voltage, tcxo_delay)) = conf.tcxo_opts {
-            self.set_dio3_as_tcxo_ctrl(tcxo_voltage, tcxo_delay)?;
-            self.wait_on_busy_async().await?;
-        }
-
-        // Calibrate
-        self.calibrate(conf.calib_param)?;
-        self.wait_on_busy_async().await?;
-        self.calibrate_image(CalibImageFreq::from_rf_frequency(conf.rf_frequency))?;
-        self.wait_on_busy_async().await?;
-
-        // 4. Define the Power Amplifier configuration with the command SetPaConfig(...)
-        self.set_pa_config(&conf.pa_config)?;
-        self.wait_on_busy_async().await?;
-
-        // 5. Define output power and ramping time with the command SetTxParams(...)
-        self.set_tx_params(&conf.tx_params)?;
-        self.wait_on_busy_async().await?;
-
-        // 6. Define where the data payload will be stored with the command SetBufferBaseAddress(...)
-        self.set_buffer_base_address(0x00, 0x00)?;
-        self.wait_on_busy_async().await?;
-
-        // 7. Send the payload to the data buffer with the command WriteBuffer(...)
-        // This is done later in SX126x::write_bytes
-
-        // 8. Define the modulation parameter according to the chosen protocol with the command SetModulationParams(...) 1
-        self.set_mod_params(&conf.mod_params)?;
-        self.wait_on_busy_async().await?;
+    /// When enabled, every command that returns a Status fails with SxError::Command
+    /// if the modem reports CommandProcessingError or FailureToExecute, so an invalid
+    /// command or sequence is caught where it happens. Disabled by default
+    pub fn set_check_status(&mut self, enabled: bool) {
+        self.driver.set_check_status(enabled);
+    }
 
-        // 9. Define the frame format to be used with the command SetPacketParams(...) 2
-        if let Some(ref packet_params) = conf.packet_params {
-            self.set_packet_params(packet_params)?;
-            self.wait_on_busy_async().await?;
-        }
+    /// Put the modem in sleep mode. Commands fail with SxError::Asleep until
+    /// the modem is woken up again with SX126x::wake or SX126x::wake_async
+    pub fn sleep(&mut self, sleep_config: SleepConfig) -> Result<(), SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.sleep(sleep_config))
+    }
 
-        // 10. Configure DIO and IRQ: use the command SetDioIrqParams(...) to select TxDone IRQ and map this IRQ to a DIO (DIO1,
-        // DIO2 or DIO3)
-        self.set_dio_irq_params(
-            conf.dio1_irq_mask,
-            conf.dio1_irq_mask,
-            conf.dio2_irq_mask,
-            conf.dio3_irq_mask,
-        )?;
-        self.wait_on_busy_async().await?;
-        self.set_dio2_as_rf_switch_ctrl(true)?;
-        self.wait_on_busy_async().await?;
+    /// Set the LoRa Sync word
+    /// Use 0x3444 for public networks like TTN
+    /// Use 0x1424 for private networks
+    pub fn set_sync_word(&mut self, sync_word: u16) -> Result<Status, SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.set_sync_word(sync_word))
+    }
 
-        // 11. Define Sync Word value: use the command WriteReg(...) to write the value of the register via direct register access
-        self.set_sync_word(conf.sync_word)?;
-        self.wait_on_busy_async().await?;
+    /// Set the GFSK sync word, 1 to 8 bytes long. The number of bits the
+    /// modem actually uses is set with GfskPacketParams::sync_word_len
+    pub fn set_gfsk_sync_word(&mut self, sync_word: &[u8]) -> Result<Status, SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.set_gfsk_sync_word(sync_word))
+    }
 
-        // Save the config for later use
-        self.config = conf;
+    /// Set the polynomial and initial value used to compute the GFSK CRC
+    pub fn set_gfsk_crc(&mut self, crc: GfskCrc) -> Result<Status, SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.set_gfsk_crc(crc))
+    }
 
-        // The rest of the steps are done by the user
-        Ok(())
+    /// Set the 9-bit initial value of the GFSK whitening LFSR.
+    /// The 7 MSB of the WhiteningInitialValueMsb register are preserved
+    pub fn set_whitening_seed(&mut self, seed: u16) -> Result<Status, SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.set_whitening_seed(seed))
     }
 
-    // pub fn init(&mut self, conf: Config) -> Result<(), SxError<TSPIERR, TPINERR>> {
-    //     futures_lite::future::block_on(self.init_async(conf))?;
-    // }
+    /// Set the node address used for GFSK address filtering
+    pub fn set_gfsk_node_address(&mut self, address: u8) -> Result<Status, SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.set_gfsk_node_address(address))
+    }
 
-    /// Set the LoRa Sync word
-    /// Use 0x3444 for public networks like TTN
-    /// Use 0x1424 for private networks
-    pub fn set_sync_word(&mut self, sync_word: u16) -> Result<(), SxError<TSPIERR, TPINERR>> {
-        self.write_register(Register::LoRaSyncWordMsb, &sync_word.to_be_bytes())
+    /// Set the broadcast address used for GFSK address filtering
+    pub fn set_gfsk_broadcast_address(&mut self, address: u8) -> Result<Status, SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.set_gfsk_broadcast_address(address))
     }
 
     /// Set the modem packet type, which can be either GFSK of LoRa
-    /// Note: GFSK is not fully supported by this crate at the moment
     pub fn set_packet_type(
         &mut self,
         packet_type: PacketType,
-    ) -> Result<(), SxError<TSPIERR, TPINERR>> {
-        self.spi
-            .write(&[0x8A, packet_type as u8])
-            .map_err(SpiError::Write)
-            .map_err(Into::into)
+    ) -> Result<Status, SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.set_packet_type(packet_type))
     }
 
-    /// The command GetPacketType() returns the current operating packet type of the radio. 
-    /// 
+    /// The command GetPacketType() returns the current operating packet type of the radio.
+    ///
     /// |      Byte      |        0      |    1   |      2     |
     /// |----------------|---------------|--------|------------|
     /// | Data from host | Opcode = 0x11 | NOP    | NOP        |
     /// | Data to host   | RFU           | Status | packetType |
-    /// 
+    ///
     /// get_packet_type will not be called in the sx126x-rs library
-    /// 
+    ///
     /// Ensure that you only use commands supported by the current radio mode.
     pub fn get_packet_type(&mut self) -> Result<PacketType, SxError<TSPIERR, TPINERR>> {
-        let mut result = [0x11, NOP, NOP];
-        self.spi
-            .transfer_in_place(&mut result)
-            .map_err(SpiError::Transfer)?;
-        Ok(result[2].into())
+        block_on(self.driver.get_packet_type())
     }
 
     /// Put the modem in standby mode
@@ -190,21 +153,36 @@ where
         &mut self,
 
         standby_config: StandbyConfig,
-    ) -> Result<(), SxError<TSPIERR, TPINERR>> {
-        self.spi
-            .write(&[OperatingModes::SetStandby.into(), standby_config as u8])
-            .map_err(SpiError::Write)
-            .map_err(Into::into)
+    ) -> Result<Status, SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.set_standby(standby_config))
+    }
+
+    /// Select the regulator used by the modem. Only use RegulatorMode::DcDc
+    /// if the DC-DC inductor is fitted on the board
+    pub fn set_regulator_mode(
+        &mut self,
+        regulator_mode: RegulatorMode,
+    ) -> Result<Status, SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.set_regulator_mode(regulator_mode))
+    }
+
+    /// Select the mode the modem goes into after TX or RX completes.
+    /// FallbackMode::Fs gives the fastest TX to RX turnaround
+    pub fn set_rx_tx_fallback_mode(
+        &mut self,
+        fallback_mode: FallbackMode,
+    ) -> Result<Status, SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.set_rx_tx_fallback_mode(fallback_mode))
     }
 
     /// Set the CAD parameters
-    /// 
+    ///
     /// | Byte | 0             | 1          | 2          | 3          | 4          | 6-7                |
     /// |------|---------------|------------|------------|------------|------------|--------------------|
     /// | Data from host | Opcode = 0x88 | cadSymbolNum | cadDetPeak  | cadDetMin  | cadExitMode | cadTimeout(23:0) |
-    /// 
+    ///
     /// Table 13-73: Recommended Settings for cadDetPeak and cadDetMin with 4 Symbols Detection
-    /// 
+    ///
     /// | SF | cadDetPeak | cadDetMin |
     /// |----|------------|-----------|
     /// | 5  | 18         | 10        |
@@ -215,89 +193,50 @@ where
     /// | 10 | 23         | 10        |
     /// | 11 | 24         | 10        |
     /// | 12 | 25         | 10        |
-    /// 
+    ///
     /// More performance tables can be found at "Application Note: SX126x CAD Performance Evaluation"
-    /// 
+    ///
     /// https://semtech.my.salesforce.com/sfc/p/#E0000000JelG/a/2R000000Q1ES/SPexo9njbhEQLcJVUg1i0Su8p3tpAtwX1jhMBGlXsQI
     pub fn set_cad_config(
         &mut self,
         cad_params: CadParams,
-    ) -> Result<(), SxError<TSPIERR, TPINERR>> {
-
-        let (timeout_high, timeout_mid, timeout_low) = match cad_params.timeout {
-            Some(timeout) => CadTimeout::split_u24(timeout.into()),
-            None => (NOP, NOP, NOP),
-        };
-
-        let params = [
-            CadCommands::SetCadParams.into(),
-            cad_params.symbol_num.into(),
-            cad_params.det_peak.into(),
-            cad_params.det_min.into(),
-            cad_params.exit_mode.into(),
-            timeout_high,
-            timeout_mid,
-            timeout_low,
-        ];
-        self.spi
-            .write(&params)
-            .map_err(SpiError::Write)
-            .map_err(Into::into)
+    ) -> Result<Status, SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.set_cad_config(cad_params))
     }
 
     /// Set the modem into CAD mode, 0xC5
-    pub fn set_cad_mode(&mut self) -> Result<(), SxError<TSPIERR, TPINERR>> {
-        self.spi
-            .write(&[OperatingModes::SetCad.into()])
-            .map_err(SpiError::Write)
-            .map_err(Into::into)
+    pub fn set_cad_mode(&mut self) -> Result<Status, SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.set_cad_mode())
     }
 
     /// Get the current status of the modem
     pub fn get_status(&mut self) -> Result<Status, SxError<TSPIERR, TPINERR>> {
-        let mut result = [0xC0, NOP];
-        self.spi
-            .transfer_in_place(&mut result)
-            .map_err(SpiError::Transfer)?;
-
-        Ok(result[1].into())
+        block_on(self.driver.get_status())
     }
 
-    pub fn set_fs(&mut self) -> Result<(), SxError<TSPIERR, TPINERR>> {
-        self.spi.write(&[OperatingModes::SetFs.into()]).map_err(SpiError::Write)?;
-        Ok(())
+    pub fn set_fs(&mut self) -> Result<Status, SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.set_fs())
     }
 
     pub fn get_stats(&mut self) -> Result<Stats, SxError<TSPIERR, TPINERR>> {
-        let mut result = [0x10, NOP, NOP, NOP, NOP, NOP, NOP, NOP];
-        self.spi
-            .transfer_in_place(&mut result)
-            .map_err(SpiError::Transfer)?;
-
-        Ok(TryInto::<[u8; 7]>::try_into(&result[1..]).unwrap().into())
+        block_on(self.driver.get_stats())
     }
 
-    /// Calibrate image
+    /// Calibrate image for the given band. Use CalibImageFreq::from_range to
+    /// cover all frequencies the modem will hop between.
+    /// The modem only calibrates in STDBY_RC, so this fails with SxError::Command
+    /// if the status reports a command error or any other mode
     pub fn calibrate_image(
         &mut self,
 
         freq: CalibImageFreq,
-    ) -> Result<(), SxError<TSPIERR, TPINERR>> {
-        let freq: [u8; 2] = freq.into();
-        let binding = [OperatingModes::CalibrateImage.into()];
-        let mut ops = [Operation::Write(&binding), Operation::Write(&freq)];
-        self.spi
-            .transaction(&mut ops)
-            .map_err(SpiError::Write)
-            .map_err(Into::into)
+    ) -> Result<Status, SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.calibrate_image(freq))
     }
 
     /// Calibrate modem
-    pub fn calibrate(&mut self, calib_param: CalibParam) -> Result<(), SxError<TSPIERR, TPINERR>> {
-        self.spi
-            .write(&[OperatingModes::Calibrate.into(), calib_param.into()])
-            .map_err(SpiError::Write)
-            .map_err(Into::into)
+    pub fn calibrate(&mut self, calib_param: CalibParam) -> Result<Status, SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.calibrate(calib_param))
     }
 
     /// Write data into a register
@@ -306,36 +245,45 @@ where
 
         register: Register,
         data: &[u8],
-    ) -> Result<(), SxError<TSPIERR, TPINERR>> {
-        let start_addr = (register as u16).to_be_bytes();
-        let mut ops = [
-            Operation::Write(&[0x0D]),
-            Operation::Write(&start_addr),
-            Operation::Write(data),
-        ];
-
-        self.spi.transaction(&mut ops).map_err(SpiError::Write)?;
-        Ok(())
+    ) -> Result<Status, SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.write_register(register, data))
     }
 
-    /// Read data from a register
+    /// Read data from a register. Multiple bytes are read
+    /// from consecutive addresses, starting at register
     pub fn read_register(
         &mut self,
 
-        start_addr: u16,
+        register: Register,
         result: &mut [u8],
-    ) -> Result<(), SxError<TSPIERR, TPINERR>> {
-        debug_assert!(!result.is_empty());
-        let start_addr = start_addr.to_be_bytes();
+    ) -> Result<Status, SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.read_register(register, result))
+    }
 
-        let mut ops = [
-            Operation::Write(&[0x1D]),
-            Operation::Write(&start_addr),
-            Operation::Read(result),
-        ];
+    /// Read a single register
+    pub fn read_reg(&mut self, register: Register) -> Result<u8, SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.read_reg(register))
+    }
 
-        self.spi.transaction(&mut ops).map_err(SpiError::Transfer)?;
-        Ok(())
+    /// Read a 16-bit value, MSB first, from register and the one after it
+    pub fn read_reg_u16(&mut self, register: Register) -> Result<u16, SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.read_reg_u16(register))
+    }
+
+    /// Read a 32-bit value, MSB first, from register and the three after it
+    pub fn read_reg_u32(&mut self, register: Register) -> Result<u32, SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.read_reg_u32(register))
+    }
+
+    /// Read a register, update its value with f and write it back.
+    /// Returns the status of the write, or of the read if that reported
+    /// a command error, along with the value written
+    pub fn modify_reg(
+        &mut self,
+        register: Register,
+        f: impl FnOnce(u8) -> u8,
+    ) -> Result<(Status, u8), SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.modify_reg(register, f))
     }
 
     /// Write data into the buffer at the defined offset
@@ -343,13 +291,8 @@ where
         &mut self,
         offset: u8,
         data: &[u8],
-    ) -> Result<(), SxError<TSPIERR, TPINERR>> {
-        let header = [0x0E, offset];
-        let mut ops = [Operation::Write(&header), Operation::Write(data)];
-        self.spi
-            .transaction(&mut ops)
-            .map_err(SpiError::Write)
-            .map_err(Into::into)
+    ) -> Result<Status, SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.write_buffer(offset, data))
     }
 
     /// Read data from the data from the defined offset
@@ -357,13 +300,8 @@ where
         &mut self,
         offset: u8,
         result: &mut [u8],
-    ) -> Result<(), SxError<TSPIERR, TPINERR>> {
-        let header = [0x1E, offset, NOP];
-        let mut ops = [Operation::Write(&header), Operation::Read(result)];
-        self.spi
-            .transaction(&mut ops)
-            .map_err(SpiError::Transfer)
-            .map_err(Into::into)
+    ) -> Result<Status, SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.read_buffer(offset, result))
     }
 
     /// Configure the dio2 pin as RF control switch
@@ -371,20 +309,20 @@ where
         &mut self,
 
         enable: bool,
-    ) -> Result<(), SxError<TSPIERR, TPINERR>> {
-        self.spi
-            .write(&[0x9D, enable as u8])
-            .map_err(SpiError::Write)
-            .map_err(Into::into)
+    ) -> Result<Status, SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.set_dio2_as_rf_switch_ctrl(enable))
     }
 
-    pub fn get_packet_status(&mut self) -> Result<PacketStatus, SxError<TSPIERR, TPINERR>> {
-        let header = [0x14, NOP];
-        let mut result = [NOP; 3];
-        let mut ops = [Operation::Write(&header), Operation::Read(&mut result)];
-        self.spi.transaction(&mut ops).map_err(SpiError::Transfer)?;
+    /// Get the status of the last received LoRa packet, along with the command status
+    pub fn get_packet_status(&mut self) -> Result<(Status, PacketStatus), SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.get_packet_status())
+    }
 
-        Ok(result.into())
+    /// Get the status of the last received GFSK packet.
+    /// A packet dropped by the address filter is reported through
+    /// GfskRxStatus::address_err
+    pub fn get_gfsk_packet_status(&mut self) -> Result<(Status, GfskPacketStatus), SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.get_gfsk_packet_status())
     }
 
     /// Configure the dio3 pin as TCXO control switch
@@ -393,57 +331,28 @@ where
 
         tcxo_voltage: TcxoVoltage,
         tcxo_delay: TcxoDelay,
-    ) -> Result<(), SxError<TSPIERR, TPINERR>> {
-        let header = [0x97, tcxo_voltage as u8];
-        let tcxo_delay: [u8; 3] = tcxo_delay.into();
-        let mut ops = [Operation::Write(&header), Operation::Write(&tcxo_delay)];
-        self.spi
-            .transaction(&mut ops)
-            .map_err(SpiError::Write)
-            .map_err(Into::into)
+    ) -> Result<Status, SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.set_dio3_as_tcxo_ctrl(tcxo_voltage, tcxo_delay))
     }
 
     /// Clear device error register
-    pub fn clear_device_errors(&mut self) -> Result<(), SxError<TSPIERR, TPINERR>> {
-        self.spi
-            .write(&[0x07, NOP, NOP])
-            .map_err(SpiError::Write)
-            .map_err(Into::into)
+    pub fn clear_device_errors(&mut self) -> Result<Status, SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.clear_device_errors())
     }
 
     /// Get current device errors
     pub fn get_device_errors(&mut self) -> Result<DeviceErrors, SxError<TSPIERR, TPINERR>> {
-        let mut result = [0x17, NOP, NOP, NOP];
-        self.spi
-            .transfer_in_place(&mut result)
-            .map_err(SpiError::Transfer)?;
-        Ok(DeviceErrors::from(u16::from_le_bytes(
-            result[2..].try_into().unwrap(),
-        )))
+        block_on(self.driver.get_device_errors())
     }
 
     /// Reset the device py pulling nrst low for a while
     pub fn reset(&mut self) -> Result<(), SxError<TSPIERR, TPINERR>> {
-        critical_section::with(|_| {
-            self.nrst_pin.set_low().map_err(PinError::Output)?;
-            // 8.1: The pin should be held low for typically 100 μs for the Reset to happen
-            self.spi
-                .transaction(&mut [Operation::DelayNs(200_000)])
-                .map_err(SpiError::Write)?;
-            self.nrst_pin
-                .set_high()
-                .map_err(PinError::Output)
-                .map_err(Into::into)
-        })
+        block_on(self.driver.reset())
     }
 
     /// Enable antenna
     pub fn set_ant_enabled(&mut self, enabled: bool) -> Result<(), TPINERR> {
-        if enabled {
-            self.ant_pin.set_high()
-        } else {
-            self.ant_pin.set_low()
-        }
+        self.driver.set_ant_enabled(enabled)
     }
 
     /// Configure IRQ
@@ -454,136 +363,128 @@ where
         dio1_mask: IrqMask,
         dio2_mask: IrqMask,
         dio3_mask: IrqMask,
-    ) -> Result<(), SxError<TSPIERR, TPINERR>> {
-        let irq = (Into::<u16>::into(irq_mask)).to_be_bytes();
-        let dio1 = (Into::<u16>::into(dio1_mask)).to_be_bytes();
-        let dio2 = (Into::<u16>::into(dio2_mask)).to_be_bytes();
-        let dio3 = (Into::<u16>::into(dio3_mask)).to_be_bytes();
-        let mut ops = [
-            Operation::Write(&[0x08]),
-            Operation::Write(&irq),
-            Operation::Write(&dio1),
-            Operation::Write(&dio2),
-            Operation::Write(&dio3),
-        ];
-        self.spi
-            .transaction(&mut ops)
-            .map_err(SpiError::Transfer)
-            .map_err(Into::into)
-    }
-
-    /// Get the current IRQ status
-    pub fn get_irq_status(&mut self) -> Result<IrqStatus, SxError<TSPIERR, TPINERR>> {
-        let mut status = [NOP, NOP, NOP];
-        let mut ops = [Operation::Write(&[0x12]), Operation::Read(&mut status)];
-        self.spi.transaction(&mut ops).map_err(SpiError::Transfer)?;
-        let irq_status: [u8; 2] = [status[1], status[2]];
-        Ok(u16::from_be_bytes(irq_status).into())
+    ) -> Result<Status, SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.set_dio_irq_params(irq_mask, dio1_mask, dio2_mask, dio3_mask))
+    }
+
+    /// Get the current IRQ status, along with the command status
+    pub fn get_irq_status(&mut self) -> Result<(Status, IrqStatus), SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.get_irq_status())
     }
 
     /// Clear the IRQ status
-    pub fn clear_irq_status(&mut self, mask: IrqMask) -> Result<(), SxError<TSPIERR, TPINERR>> {
-        let mask = Into::<u16>::into(mask).to_be_bytes();
-        let mut ops = [Operation::Write(&[0x02]), Operation::Write(&mask)];
-        self.spi
-            .transaction(&mut ops)
-            .map_err(SpiError::Write)
-            .map_err(Into::into)
+    pub fn clear_irq_status(&mut self, mask: IrqMask) -> Result<Status, SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.clear_irq_status(mask))
     }
 
     /// Put the device in TX mode. It will start sending the data written in the buffer,
     /// starting at the configured offset
     pub fn set_tx(&mut self, timeout: RxTxTimeout) -> Result<Status, SxError<TSPIERR, TPINERR>> {
-        let mut buf = [OperatingModes::SetTx.into(); 4];
-        let timeout: [u8; 3] = timeout.into();
-        buf[1..].copy_from_slice(&timeout);
-
-        self.spi
-            .transfer_in_place(&mut buf)
-            .map_err(SpiError::Transfer)?;
-        Ok(timeout[1].into())
+        block_on(self.driver.set_tx(timeout))
     }
 
     pub fn set_rx(&mut self, timeout: RxTxTimeout) -> Result<Status, SxError<TSPIERR, TPINERR>> {
-        let mut buf = [OperatingModes::SetRx.into(); 4];
-        let timeout: [u8; 3] = timeout.into();
-        buf[1..].copy_from_slice(&timeout);
+        block_on(self.driver.set_rx(timeout))
+    }
+
+    /// Put the device in RX mode for a window of the given number of symbols, calculated
+    /// from the current LoRa modulation parameters. Use together with
+    /// SX126x::stop_timer_on_preamble to keep receiving a packet that starts inside the window.
+    /// Fails with SxError::InvalidConfig for 0 symbols, which would disable the timeout
+    pub fn set_rx_symbols(&mut self, symbols: u16) -> Result<Status, SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.set_rx_symbols(symbols))
+    }
+
+    /// Select the event that stops the RX timeout timer: preamble detection if enabled,
+    /// otherwise sync word (GFSK) or header (LoRa) detection
+    pub fn stop_timer_on_preamble(&mut self, enable: bool) -> Result<Status, SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.stop_timer_on_preamble(enable))
+    }
+
+    /// Set the number of symbols the modem waits for to validate a reception.
+    /// The RX timeout occurs when no LoRa preamble is detected within this number of
+    /// symbols. A value of 0 makes the modem validate the reception as soon as a
+    /// LoRa symbol has been detected. Values are rounded up as described for
+    /// LoRaSymbNumTimeout. Fails with SxError::InvalidConfig above
+    /// LORA_SYMB_NUM_TIMEOUT_MAX symbols
+    pub fn set_lora_symb_num_timeout(&mut self, symb_num: u8) -> Result<Status, SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.set_lora_symb_num_timeout(symb_num))
+    }
 
-        self.spi.write(&buf).map_err(SpiError::Transfer)?;
-        Ok(timeout[0].into())
+    /// Start transmitting an unmodulated carrier at the configured frequency and output power,
+    /// for regulatory pre-scans and antenna tuning. The transmission runs until
+    /// TestTx::stop_async is called
+    pub fn set_tx_continuous_wave(&mut self) -> Result<TestTx<'_, Self>, SxError<TSPIERR, TPINERR>> {
+        self.start_test_tx(OperatingModes::SetTxContinuousWave)
+    }
+
+    /// Start transmitting an infinite sequence of preamble symbols, using the configured
+    /// modulation. The transmission runs until TestTx::stop_async is called
+    pub fn set_tx_infinite_preamble(&mut self) -> Result<TestTx<'_, Self>, SxError<TSPIERR, TPINERR>> {
+        self.start_test_tx(OperatingModes::SetTxInfinitePreamble)
+    }
+
+    fn start_test_tx(
+        &mut self,
+        opcode: OperatingModes,
+    ) -> Result<TestTx<'_, Self>, SxError<TSPIERR, TPINERR>> {
+        let previous_mode = block_on(self.driver.start_test_tx(opcode))?;
+        Ok(TestTx::new(self, previous_mode, Self::abort_test_tx))
+    }
+
+    /// Best effort to end a test transmission when its TestTx is dropped
+    fn abort_test_tx(&mut self) {
+        let _ = self.set_standby(StandbyConfig::StbyRc);
+    }
+
+    /// Put the device in RX duty cycle (listen) mode. The modem alternates between
+    /// listening for rx_period and sleeping for sleep_period, both in steps of
+    /// 15.625 μs. When a preamble is detected, the modem stays in RX until
+    /// the packet is received. RxDutyCycle::from_lora_preamble derives
+    /// suitable periods from the modulation parameters
+    pub fn set_rx_duty_cycle(
+        &mut self,
+        rx_period: RxTxTimeout,
+        sleep_period: RxTxTimeout,
+    ) -> Result<Status, SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.set_rx_duty_cycle(rx_period, sleep_period))
     }
 
     /// Set packet parameters
     pub fn set_packet_params(
         &mut self,
         params: &PacketParams,
-    ) -> Result<(), SxError<TSPIERR, TPINERR>> {
-        let params: [u8; 9] = params.into();
-        let mut ops = [Operation::Write(&[0x8C]), Operation::Write(&params)];
-        self.spi
-            .transaction(&mut ops)
-            .map_err(SpiError::Write)
-            .map_err(Into::into)
+    ) -> Result<Status, SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.set_packet_params(params))
     }
 
-    /// Set modulation parameters
-    pub fn set_mod_params(&mut self, params: &ModParams) -> Result<(), SxError<TSPIERR, TPINERR>> {
-        let params: [u8; 8] = params.into();
-        let mut ops = [Operation::Write(&[0x8B]), Operation::Write(&params)];
-        self.spi
-            .transaction(&mut ops)
-            .map_err(SpiError::Write)
-            .map_err(Into::into)
+    /// Set modulation parameters. Fails with SxError::InvalidConfig if low data rate
+    /// optimization is forced to a value that contradicts the symbol time, unless it
+    /// was set with LoraModParams::override_low_dr_opt
+    pub fn set_mod_params(&mut self, params: &ModParams) -> Result<Status, SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.set_mod_params(params))
     }
 
     /// Set TX parameters
-    pub fn set_tx_params(&mut self, params: &TxParams) -> Result<(), SxError<TSPIERR, TPINERR>> {
-        let params: [u8; 2] = params.into();
-        let mut ops = [Operation::Write(&[0x8E]), Operation::Write(&params)];
-        self.spi
-            .transaction(&mut ops)
-            .map_err(SpiError::Write)
-            .map_err(Into::into)
-    }
-
-    /// Set RF frequency. This writes the passed rf_freq directly to the modem.
-    /// Use sx1262::calc_rf_freq to calulate the correct value based
-    /// On the XTAL frequency and the desired RF frequency
-    pub fn set_rf_frequency(&mut self, rf_freq: u32) -> Result<(), SxError<TSPIERR, TPINERR>> {
-        let rf_freq = rf_freq.to_be_bytes();
-        let mut ops = [Operation::Write(&[0x86]), Operation::Write(&rf_freq)];
-        self.spi
-            .transaction(&mut ops)
-            .map_err(SpiError::Write)
-            .map_err(Into::into)
+    pub fn set_tx_params(&mut self, params: &TxParams) -> Result<Status, SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.set_tx_params(params))
     }
 
-    /// Set Power Amplifier configuration
-    pub fn set_pa_config(&mut self, pa_config: &PaConfig) -> Result<(), SxError<TSPIERR, TPINERR>> {
-        let pa_config: [u8; 4] = pa_config.into();
-        let binding = [OperatingModes::SetPaConfig.into()];
-        let mut ops = [Operation::Write(&binding), Operation::Write(&pa_config[..3])];
-        let _ = self.spi
-            .transaction(&mut ops)
-            .map_err(SpiError::Write)
-            .map_err(|e| Into::<SxError<TSPIERR, TPINERR>>::into(e));
-
-        // Now we need to apply the clamping fix for the PA
-        // Read current clamping configuration
-        let mut clamp_config: u8 = 0;
-        self.read_register(Register::TxClampConfig.into(), &mut [clamp_config])?;
-
-        // If we should apply or remove the fix
-        if pa_config[3] != 0 {
-                // Apply fix
-                clamp_config |= 0x1E;
-        } else {
-                // Remove fix (This is default behavior)
-                clamp_config = (clamp_config & !0x1E) | 0x08;
-        }
+    /// Set RF frequency. The frequency is converted to PLL steps
+    /// using the XTAL frequency from the Config.
+    /// If the frequency lies outside the band of the last image calibration,
+    /// the image is calibrated again. The modem only calibrates in STDBY_RC, so it is
+    /// put in STDBY_RC first if it is in STDBY_XOSC or FS, and back into that mode afterwards
+    pub fn set_rf_frequency(
+        &mut self,
+        rf_frequency: Frequency,
+    ) -> Result<Status, SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.set_rf_frequency(rf_frequency))
+    }
 
-        // Write back the new configuration
-        self.write_register(Register::TxClampConfig, &[clamp_config])
+    /// Set Power Amplifier configuration
+    pub fn set_pa_config(&mut self, pa_config: &PaConfig) -> Result<Status, SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.set_pa_config(pa_config))
     }
 
     /// Configure the base addresses in the buffer
@@ -592,29 +493,44 @@ where
 
         tx_base_addr: u8,
         rx_base_addr: u8,
-    ) -> Result<(), SxError<TSPIERR, TPINERR>> {
-        self.spi
-            .write(&[0x8F, tx_base_addr, rx_base_addr])
-            .map_err(SpiError::Write)
-            .map_err(Into::into)
-    }
-
-    pub fn fix_sensitivity(&mut self) -> Result<(), SxError<TSPIERR, TPINERR>> {
-        // Read the current sensitivity configuration
-        let mut sensitivity_config: u8 = 0;
-        self.read_register(Register::SensitivityConfig.into(), &mut [sensitivity_config])?;
-
-        // Check if the packet type is LoRa and the bandwidth is 500 kHz
-        if self.get_packet_type()? == PacketType::LoRa &&
-         self.config.mod_params.get_bandwidth() == LoRaBandWidth::BW500 {
-            // Apply the sensitivity fix for LoRa
-            sensitivity_config &= 0xFB;
-        } else {
-            // Remove the sensitivity fix
-            sensitivity_config |= 0x04;
-        }
-        // Write the updated sensitivity configuration back to the register
-        self.write_register(Register::SensitivityConfig.into(), &[sensitivity_config])
+    ) -> Result<Status, SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.set_buffer_base_address(tx_base_addr, rx_base_addr))
+    }
+
+    pub fn fix_sensitivity(&mut self) -> Result<Status, SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.fix_sensitivity())
+    }
+
+    /// Get Rx buffer status, containing the length of the last received packet
+    /// and the address of the first byte received.
+    pub fn get_rx_buffer_status(&mut self) -> Result<RxBufferStatus, SxError<TSPIERR, TPINERR>> {
+        block_on(self.driver.get_rx_buffer_status())
+    }
+}
+
+/// The async flows of SX126x await DIO1 through AnyWait, which lets the executor run
+/// other tasks during a TX or RX. The commands themselves are still blocking SPI
+/// transfers, preceded by polling BUSY with the delay. Use SX126xAsync for an async SPI bus
+impl<TSPI, TNRST, TBUSY, TANT, TDIO1, TDELAY, TSPIERR, TPINERR> SX126x<TSPI, TNRST, TBUSY, TANT, TDIO1, TDELAY>
+where
+    TPINERR: core::fmt::Debug,
+    TSPI: SpiDevice<Error = TSPIERR>,
+    TNRST: OutputPin<Error = TPINERR>,
+    TBUSY: AnyWait<Error = TPINERR> + InputPin<Error = TPINERR>,
+    TANT: OutputPin<Error = TPINERR>,
+    TDIO1: AnyWait<Error = TPINERR>,
+    TDELAY: DelayNs,
+{
+    // Initialize and configure the SX126x using the provided Config
+    pub async fn init_async(&mut self, conf: Config) -> Result<(), SxError<TSPIERR, TPINERR>> {
+        self.driver.init(conf).await
+    }
+
+    /// Wake the modem from sleep by toggling NSS, and wait until it is
+    /// ready in STDBY_RC. After a cold start the stored Config, which the
+    /// setters keep up to date, is applied again
+    pub async fn wake_async(&mut self) -> Result<(), SxError<TSPIERR, TPINERR>> {
+        self.driver.wake().await
     }
 
     /// High level method to send a message. This methods writes the data in the buffer,
@@ -625,73 +541,110 @@ where
         &mut self,
         data: &[u8],
         timeout: RxTxTimeout,
-        params: LoRaPacketParams,
-    ) -> Result<Status, SxError<TSPIERR, TPINERR>> {
-
-        self.set_packet_params(&PacketParams::from(params))?;
+        params: impl Into<PacketParams>,
+    ) -> Result<TxOutcome, SxError<TSPIERR, TPINERR>> {
+        self.driver.transmit::<AwaitDio1>(data, timeout, params.into()).await
+    }
 
-        // Set Buffer Pointers
-        self.set_buffer_base_address(0x00, 0x00)?;
+    /// High level method to receive a message. This method puts the device in RX mode,
+    /// waits until a packet is received or a timeout occurs, and copies the payload into buf.
+    /// Timeouts, CRC errors, LoRa header errors and packets dropped by the GFSK address
+    /// filter are reported as SxError::Rx. A dropped packet raises no IRQ, so it is only
+    /// reported once the timeout elapsed. The IRQs that were handled are cleared.
+    pub async fn read_bytes_async(
+        &mut self,
+        buf: &mut [u8],
+        timeout: RxTxTimeout,
+    ) -> Result<RxPacket, SxError<TSPIERR, TPINERR>> {
+        self.driver.receive::<AwaitDio1>(buf, timeout).await
+    }
 
-        // Write data to buffer
-        self.write_buffer(0x00, data)?;
+    /// Read a 32-bit random number from the modem. The modem is put in continuous RX
+    /// with all IRQs disabled while the number is read, and is left in STDBY_RC with
+    /// the IRQ masks of the stored Config afterwards
+    pub async fn random_u32_async(&mut self) -> Result<u32, SxError<TSPIERR, TPINERR>> {
+        self.driver.random_u32().await
+    }
 
-        // Clear IRQ
-        // self.clear_irq_status(IrqMask::all())?;
+    /// Busily wait for the busy pin to go low
+    pub async fn wait_on_busy_async(&mut self) -> Result<(), SxError<TSPIERR, TPINERR>> {
+        self.driver.bus.wait_on_busy_async().await
+    }
 
-        // Fix Sensitivity
-        self.fix_sensitivity()?;
-        
-        // Set tx mode
-        let status = self.set_tx(timeout)?;
-        // Wait for busy line to go low
-        self.wait_on_busy_async().await?;
-        // Wait on dio1 going high
-        self.wait_on_dio1_async().await?;
+    /// Busily wait for the dio1 pin to go high
+    pub async fn wait_on_dio1_async(&mut self) -> Result<(), PinError<TPINERR>> {
+        self.driver.dio1_pin.anywait_for_high().await.map_err(PinError::Input)
+    }
+}
 
-        // Write completed!
-        Ok(status)
+/// Run a future of the driver over the blocking transport to completion.
+/// That transport never awaits, so the future is ready after the first poll
+fn block_on<F: Future>(future: F) -> F::Output {
+    match pin!(future).poll(&mut Context::from_waker(Waker::noop())) {
+        Poll::Ready(output) => output,
+        Poll::Pending => unreachable!("the blocking transport never returns Pending"),
     }
+}
 
-    // pub async fn write_bytes(
-    //     &mut self,
-    //     data: &[u8],
-    //     timeout: RxTxTimeout,
-    //     preamble_len: u16,
-    //     crc_type: packet::LoRaCrcType,
-    // ) -> Result<Status, SxError<TSPIERR, TPINERR>> {
-    //     futures_lite::future::block_on(self.write_bytes_async(data, timeout, preamble_len, crc_type))
-    // }
+/// Forget the sleep state after waking up. Returns true if the modem was
+/// in a cold start sleep, and has lost its configuration
+fn woke_from_cold_start(sleep_config: Option<SleepConfig>) -> bool {
+    sleep_config.is_some_and(|sleep_config| !sleep_config.warm_start)
+}
 
-    /// Get Rx buffer status, containing the length of the last received packet
-    /// and the address of the first byte received.
-    pub fn get_rx_buffer_status(&mut self) -> Result<RxBufferStatus, SxError<TSPIERR, TPINERR>> {
-        let mut result = [0x13, NOP, NOP, NOP];
-        self.spi
-            .transfer_in_place(&mut result)
-            .map_err(SpiError::Transfer)?;
-        Ok(TryInto::<[u8; 2]>::try_into(&result[2..]).unwrap().into())
+/// The status of two commands sent in sequence: the first one if it reports
+/// a command error, otherwise the second one
+fn sequence_status(first: Status, then: Status) -> Status {
+    if first.is_command_error() {
+        first
+    } else {
+        then
     }
+}
 
-    /// Busily wait for the busy pin to go low
-    pub async fn wait_on_busy_async(&mut self) -> Result<(), SxError<TSPIERR, TPINERR>> {
-        self.spi
-            .transaction(&mut [Operation::DelayNs(1000)])
-            .map_err(SpiError::Transfer)?;
+/// True once a transmission has ended
+fn tx_finished(irq_status: IrqStatus) -> bool {
+    irq_status.tx_done() || irq_status.timeout()
+}
 
-        self.busy_pin.anywait_for_low().await.map_err(|err| SxError::Pin(PinError::Input(err)))
-    }
+/// True once a reception has ended, successfully or not
+fn rx_finished(irq_status: IrqStatus) -> bool {
+    irq_status.rx_done() || irq_status.timeout() || irq_status.header_error() || irq_status.crc_err()
+}
 
-    // pub fn wait_on_busy(&mut self) -> Result<(), SxError<TSPIERR, TPINERR>> {
-    //     futures_lite::future::block_on(self.wait_on_busy_async())
-    // }
+/// Map the IRQs and GFSK packet status of a finished reception to the reason it failed, if any
+fn check_rx(irq_status: IrqStatus, gfsk_status: Option<&GfskPacketStatus>) -> Result<(), RxError> {
+    if irq_status.timeout() {
+        // A packet dropped by the GFSK address filter raises no IRQ, the reception
+        // only ends at the timeout. The address error in the packet status tells them apart
+        if gfsk_status.is_some_and(|status| status.rx_status().address_err()) {
+            return Err(RxError::AddressFiltered);
+        }
+        return Err(RxError::Timeout);
+    }
+    if irq_status.header_error() {
+        return Err(RxError::HeaderError);
+    }
+    if irq_status.crc_err() {
+        return Err(RxError::CrcErr);
+    }
+    Ok(())
+}
 
-    /// Busily wait for the dio1 pin to go high
-    pub async fn wait_on_dio1_async(&mut self) -> Result<(), PinError<TPINERR>> {
-        self.dio1_pin.anywait_for_high().await.map_err(PinError::Input)
+fn gfsk_rx_packet(len: u8, status: &GfskPacketStatus) -> RxPacket {
+    RxPacket {
+        len: len as usize,
+        rssi: status.rssi_avg(),
+        snr: None,
+        signal_rssi: status.rssi_sync(),
     }
+}
 
-    // pub fn wait_on_dio1(&mut self) -> Result<(), PinError<TPINERR>> {
-    //     futures_lite::future::block_on(self.wait_on_dio1_async())
-    // }
+fn lora_rx_packet(len: u8, status: &PacketStatus) -> RxPacket {
+    RxPacket {
+        len: len as usize,
+        rssi: status.rssi_pkt(),
+        snr: Some(status.snr_pkt()),
+        signal_rssi: status.signal_rssi_pkt(),
+    }
 }
@@ -0,0 +1,71 @@
+use core::num::NonZeroU32;
+
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::{InputPin, OutputPin};
+use embedded_hal::spi::SpiDevice;
+use rand_core::{impls, Error, RngCore};
+
+use super::SX126x;
+
+/// Error code reported through rand_core::Error when reading a random number fails
+pub const RNG_ERROR_CODE: u32 = Error::CUSTOM_START;
+
+/// The modem used as a hardware random number generator, created with SX126x::rng.
+/// Every 32 bits are read with SX126x::random_u32, which puts the modem in
+/// continuous RX and leaves it in STDBY_RC.
+///
+/// The numbers come from the noise picked up by the receiver, but are not guaranteed
+/// to be uniformly distributed. Use them to seed a cryptographically secure RNG
+/// rather than directly as key material.
+///
+/// SPI and pin errors are reported as a rand_core::Error with code RNG_ERROR_CODE.
+/// Use SX126x::random_u32 directly to handle them.
+pub struct SxRng<'a, SX> {
+    sx: &'a mut SX,
+}
+
+impl<'a, SX> SxRng<'a, SX> {
+    pub(super) fn new(sx: &'a mut SX) -> Self {
+        Self { sx }
+    }
+}
+
+impl<TSPI, TNRST, TBUSY, TANT, TDIO1, TDELAY, TSPIERR, TPINERR> RngCore
+    for SxRng<'_, SX126x<TSPI, TNRST, TBUSY, TANT, TDIO1, TDELAY>>
+where
+    TPINERR: core::fmt::Debug,
+    TSPI: SpiDevice<Error = TSPIERR>,
+    TNRST: OutputPin<Error = TPINERR>,
+    TBUSY: InputPin<Error = TPINERR>,
+    TANT: OutputPin<Error = TPINERR>,
+    TDIO1: InputPin<Error = TPINERR>,
+    TDELAY: DelayNs,
+{
+    fn next_u32(&mut self) -> u32 {
+        let mut random = [0; 4];
+        self.fill_bytes(&mut random);
+        u32::from_le_bytes(random)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        impls::next_u64_via_u32(self)
+    }
+
+    /// Panics if the modem could not be read, use try_fill_bytes to handle errors
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        if let Err(err) = self.try_fill_bytes(dest) {
+            panic!("reading random numbers from the modem failed: {}", err);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        for chunk in dest.chunks_mut(4) {
+            let random = self
+                .sx
+                .random_u32()
+                .map_err(|_| Error::from(NonZeroU32::new(RNG_ERROR_CODE).unwrap()))?;
+            chunk.copy_from_slice(&random.to_le_bytes()[..chunk.len()]);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,75 @@
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::{InputPin, OutputPin};
+use embedded_hal::spi::SpiDevice;
+
+use super::err::SxError;
+use super::flow;
+use super::wait::AnyWait;
+use super::SX126x;
+use crate::op::*;
+
+/// A running test transmission, started with SX126x::set_tx_continuous_wave or
+/// SX126x::set_tx_infinite_preamble. The modem keeps transmitting until
+/// TestTx::stop_async (or TestTx::stop) is called, which puts it back into the mode it was in
+/// before the test was started.
+/// A test transmission must be stopped, use stop to find out whether it actually ended.
+/// If the TestTx of an SX126x is dropped without a successful stop, SetStandby is sent
+/// right away as a best effort, ignoring any error. Drop cannot await, so the TestTx of
+/// an SX126xAsync only marks the test as pending: the modem keeps transmitting until the
+/// next command of the driver, which sends SetStandby first.
+#[must_use = "the modem keeps transmitting until it is stopped"]
+pub struct TestTx<'a, SX> {
+    pub(super) sx: &'a mut SX,
+    pub(super) previous_mode: ChipMode,
+    /// Ends the test, or marks it as pending, on drop. None once the test was stopped
+    abort: Option<fn(&mut SX)>,
+}
+
+impl<'a, SX> TestTx<'a, SX> {
+    pub(super) fn new(sx: &'a mut SX, previous_mode: ChipMode, abort: fn(&mut SX)) -> Self {
+        Self {
+            sx,
+            previous_mode,
+            abort: Some(abort),
+        }
+    }
+
+    /// Record the outcome of stopping the test, so drop leaves the modem alone if it succeeded
+    pub(super) fn stopped<T, E>(&mut self, result: Result<T, E>) -> Result<T, E> {
+        if result.is_ok() {
+            self.abort = None;
+        }
+        result
+    }
+}
+
+impl<SX> Drop for TestTx<'_, SX> {
+    fn drop(&mut self) {
+        if let Some(abort) = self.abort.take() {
+            abort(self.sx);
+        }
+    }
+}
+
+impl<'a, TSPI, TNRST, TBUSY, TANT, TDIO1, TDELAY, TSPIERR, TPINERR>
+    TestTx<'a, SX126x<TSPI, TNRST, TBUSY, TANT, TDIO1, TDELAY>>
+where
+    TPINERR: core::fmt::Debug,
+    TSPI: SpiDevice<Error = TSPIERR>,
+    TNRST: OutputPin<Error = TPINERR>,
+    TBUSY: AnyWait<Error = TPINERR> + InputPin<Error = TPINERR>,
+    TANT: OutputPin<Error = TPINERR>,
+    TDIO1: AnyWait<Error = TPINERR>,
+    TDELAY: DelayNs,
+{
+    /// Stop the test transmission. The modem is put in STDBY_RC, and from there
+    /// back into STDBY_XOSC or FS if it was in one of those modes before the test
+    pub async fn stop_async(mut self) -> Result<(), SxError<TSPIERR, TPINERR>> {
+        let result = self
+            .sx
+            .driver
+            .run_flow(flow::stop_test_tx(self.previous_mode))
+            .await;
+        self.stopped(result)
+    }
+}
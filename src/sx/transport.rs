@@ -0,0 +1,217 @@
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::{InputPin, OutputPin};
+use embedded_hal::spi::{Operation, SpiDevice};
+use embedded_hal_async::spi::SpiDevice as AsyncSpiDevice;
+
+use super::err::{PinError, SpiError, SxError};
+use super::wait::AnyWait;
+use super::{BUSY_TIMEOUT_US, POLL_INTERVAL_US};
+
+/// The SPI device, NRST and BUSY lines that connect the driver to the modem.
+/// SX126x and SX126xAsync share all driver logic, and only differ in their
+/// transport: how the data is transferred and how BUSY is waited on
+pub trait Transport {
+    type SpiError;
+    type PinError;
+
+    /// Run an SPI transaction once BUSY is low
+    async fn transaction(
+        &mut self,
+        operations: &mut [Operation<'_, u8>],
+    ) -> Result<(), SxError<Self::SpiError, Self::PinError>>;
+
+    /// Clock out data right away, without waiting on BUSY. Used to wake the modem
+    async fn write(&mut self, data: &[u8]) -> Result<(), SxError<Self::SpiError, Self::PinError>>;
+
+    /// Wait until BUSY goes low after a command
+    async fn wait_on_busy(&mut self) -> Result<(), SxError<Self::SpiError, Self::PinError>>;
+
+    /// Reset the modem by pulling NRST low for a while
+    async fn reset(&mut self) -> Result<(), SxError<Self::SpiError, Self::PinError>>;
+}
+
+/// Transport over a blocking SPI device. BUSY is polled, with the delay between
+/// the polls, so none of its methods ever return Pending
+pub struct BlockingTransport<TSPI, TNRST, TBUSY, TDELAY> {
+    spi: TSPI,
+    nrst_pin: TNRST,
+    busy_pin: TBUSY,
+    delay: TDELAY,
+}
+
+impl<TSPI, TNRST, TBUSY, TDELAY> BlockingTransport<TSPI, TNRST, TBUSY, TDELAY> {
+    pub fn new(spi: TSPI, nrst_pin: TNRST, busy_pin: TBUSY, delay: TDELAY) -> Self {
+        Self {
+            spi,
+            nrst_pin,
+            busy_pin,
+            delay,
+        }
+    }
+}
+
+impl<TSPI, TNRST, TBUSY, TDELAY, TPINERR> BlockingTransport<TSPI, TNRST, TBUSY, TDELAY>
+where
+    TBUSY: InputPin<Error = TPINERR>,
+    TDELAY: DelayNs,
+{
+    /// Poll the dio1 pin until it goes high. Fails with SxError::Timeout
+    /// after timeout_us μs, or waits indefinitely if no timeout is given
+    pub(super) fn poll_dio1<TSPIERR, TDIO1: InputPin<Error = TPINERR>>(
+        &mut self,
+        dio1_pin: &mut TDIO1,
+        timeout_us: Option<u32>,
+    ) -> Result<(), SxError<TSPIERR, TPINERR>> {
+        let mut elapsed_us = 0;
+        while dio1_pin.is_low().map_err(PinError::Input)? {
+            if timeout_us.is_some_and(|timeout_us| elapsed_us >= timeout_us) {
+                return Err(SxError::Timeout);
+            }
+            self.delay.delay_us(POLL_INTERVAL_US);
+            elapsed_us += POLL_INTERVAL_US;
+        }
+        Ok(())
+    }
+
+    /// Poll the busy pin until it goes low. Fails with SxError::Timeout
+    /// if the modem stays busy for longer than 100 ms
+    fn poll_busy<TSPIERR>(&mut self) -> Result<(), SxError<TSPIERR, TPINERR>> {
+        let mut elapsed_us = 0;
+        while self.busy_pin.is_high().map_err(PinError::Input)? {
+            if elapsed_us >= BUSY_TIMEOUT_US {
+                return Err(SxError::Timeout);
+            }
+            self.delay.delay_us(POLL_INTERVAL_US);
+            elapsed_us += POLL_INTERVAL_US;
+        }
+        Ok(())
+    }
+}
+
+impl<TSPI, TNRST, TBUSY, TDELAY, TPINERR> BlockingTransport<TSPI, TNRST, TBUSY, TDELAY>
+where
+    TBUSY: AnyWait<Error = TPINERR>,
+    TDELAY: DelayNs,
+{
+    /// Wait for the busy pin to go low through AnyWait
+    pub(super) async fn wait_on_busy_async<TSPIERR>(&mut self) -> Result<(), SxError<TSPIERR, TPINERR>> {
+        // BUSY goes high at most 600 ns after the command ends
+        self.delay.delay_us(1);
+        self.busy_pin
+            .anywait_for_low()
+            .await
+            .map_err(|err| SxError::Pin(PinError::Input(err)))
+    }
+}
+
+impl<TSPI, TNRST, TBUSY, TDELAY, TSPIERR, TPINERR> Transport for BlockingTransport<TSPI, TNRST, TBUSY, TDELAY>
+where
+    TSPI: SpiDevice<Error = TSPIERR>,
+    TNRST: OutputPin<Error = TPINERR>,
+    TBUSY: InputPin<Error = TPINERR>,
+    TDELAY: DelayNs,
+{
+    type SpiError = TSPIERR;
+    type PinError = TPINERR;
+
+    /// Poll BUSY until it is low, then run the transaction. Fails with
+    /// SxError::Timeout if the modem stays busy for longer than 100 ms
+    async fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), SxError<TSPIERR, TPINERR>> {
+        self.poll_busy()?;
+        self.spi.transaction(operations).map_err(SpiError::Transfer)?;
+        Ok(())
+    }
+
+    async fn write(&mut self, data: &[u8]) -> Result<(), SxError<TSPIERR, TPINERR>> {
+        self.spi.write(data).map_err(SpiError::Write)?;
+        Ok(())
+    }
+
+    async fn wait_on_busy(&mut self) -> Result<(), SxError<TSPIERR, TPINERR>> {
+        // BUSY goes high at most 600 ns after the command ends
+        self.delay.delay_us(1);
+        self.poll_busy()
+    }
+
+    async fn reset(&mut self) -> Result<(), SxError<TSPIERR, TPINERR>> {
+        critical_section::with(|_| {
+            self.nrst_pin.set_low().map_err(PinError::Output)?;
+            // 8.1: The pin should be held low for typically 100 μs for the Reset to happen
+            self.delay.delay_us(200);
+            self.nrst_pin
+                .set_high()
+                .map_err(PinError::Output)
+                .map_err(Into::into)
+        })
+    }
+}
+
+/// Transport over an async SPI device, so a DMA-backed SPI peripheral can yield
+/// to the executor while the data is clocked out. BUSY is waited on through AnyWait
+pub struct AsyncTransport<TSPI, TNRST, TBUSY> {
+    spi: TSPI,
+    nrst_pin: TNRST,
+    busy_pin: TBUSY,
+}
+
+impl<TSPI, TNRST, TBUSY> AsyncTransport<TSPI, TNRST, TBUSY> {
+    pub fn new(spi: TSPI, nrst_pin: TNRST, busy_pin: TBUSY) -> Self {
+        Self {
+            spi,
+            nrst_pin,
+            busy_pin,
+        }
+    }
+}
+
+impl<TSPI, TNRST, TBUSY, TSPIERR, TPINERR> Transport for AsyncTransport<TSPI, TNRST, TBUSY>
+where
+    TSPI: AsyncSpiDevice<Error = TSPIERR>,
+    TNRST: OutputPin<Error = TPINERR>,
+    TBUSY: AnyWait<Error = TPINERR>,
+{
+    type SpiError = TSPIERR;
+    type PinError = TPINERR;
+
+    async fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), SxError<TSPIERR, TPINERR>> {
+        self.busy_pin
+            .anywait_for_low()
+            .await
+            .map_err(PinError::Input)?;
+        self.spi
+            .transaction(operations)
+            .await
+            .map_err(SpiError::Transfer)?;
+        Ok(())
+    }
+
+    async fn write(&mut self, data: &[u8]) -> Result<(), SxError<TSPIERR, TPINERR>> {
+        self.spi.write(data).await.map_err(SpiError::Write)?;
+        Ok(())
+    }
+
+    async fn wait_on_busy(&mut self) -> Result<(), SxError<TSPIERR, TPINERR>> {
+        self.spi
+            .transaction(&mut [Operation::DelayNs(1000)])
+            .await
+            .map_err(SpiError::Transfer)?;
+
+        self.busy_pin
+            .anywait_for_low()
+            .await
+            .map_err(|err| SxError::Pin(PinError::Input(err)))
+    }
+
+    async fn reset(&mut self) -> Result<(), SxError<TSPIERR, TPINERR>> {
+        self.nrst_pin.set_low().map_err(PinError::Output)?;
+        // 8.1: The pin should be held low for typically 100 μs for the Reset to happen
+        self.spi
+            .transaction(&mut [Operation::DelayNs(200_000)])
+            .await
+            .map_err(SpiError::Write)?;
+        self.nrst_pin
+            .set_high()
+            .map_err(PinError::Output)
+            .map_err(Into::into)
+    }
+}
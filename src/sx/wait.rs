@@ -1,4 +1,4 @@
-use embedded_hal::digital::InputPin;
+use embedded_hal::digital::{ErrorType, InputPin};
 use embedded_hal_async::digital::Wait;
 
 /*
@@ -43,6 +43,20 @@ impl<T: InputPin> AnyWait for PollingInputPin<T> {
     }
 }
 
+impl<T: InputPin> ErrorType for PollingInputPin<T> {
+    type Error = T::Error;
+}
+
+impl<T: InputPin> InputPin for PollingInputPin<T> {
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        self.0.is_high()
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        self.0.is_low()
+    }
+}
+
 impl<T: InputPin> From<T> for PollingInputPin<T> {
     fn from(value: T) -> Self {
         Self(value)
@@ -0,0 +1,1425 @@
+//! Byte-exact checks of the command encoding against chapter 13 of the datasheet
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::rc::Rc;
+
+use embedded_hal::digital::{ErrorType as PinErrorType, InputPin, OutputPin};
+use embedded_hal::spi::{ErrorType, Operation, SpiDevice};
+use rand_core::RngCore;
+use sx126x::conf::Config;
+use sx126x::mock::MockDelay;
+use sx126x::op::*;
+use sx126x::reg::Register;
+use sx126x::{SX126x, SxError};
+
+/// SPI fake recording the bytes clocked out in every transaction.
+/// The bytes clocked in are taken from the scripted responses, one per
+/// transaction and indexed by position within the transaction. Transactions
+/// without a scripted response read the idle response, zeros by default
+#[derive(Clone, Default)]
+struct RecordingSpi {
+    log: Rc<RefCell<Log>>,
+}
+
+#[derive(Default)]
+struct Log {
+    sent: Vec<Vec<u8>>,
+    responses: VecDeque<Vec<u8>>,
+    idle: Vec<u8>,
+}
+
+impl RecordingSpi {
+    /// Script the bytes clocked in during the next unanswered transaction
+    fn respond(&self, miso: &[u8]) {
+        self.log.borrow_mut().responses.push_back(miso.to_vec());
+    }
+
+    /// Script the bytes clocked in during every transaction without a scripted response
+    fn respond_idle(&self, miso: &[u8]) {
+        self.log.borrow_mut().idle = miso.to_vec();
+    }
+
+    /// Take the transactions recorded so far
+    fn take(&self) -> Vec<Vec<u8>> {
+        std::mem::take(&mut self.log.borrow_mut().sent)
+    }
+}
+
+impl ErrorType for RecordingSpi {
+    type Error = Infallible;
+}
+
+impl SpiDevice for RecordingSpi {
+    fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), Infallible> {
+        let mut log = self.log.borrow_mut();
+        let miso = log
+            .responses
+            .pop_front()
+            .unwrap_or_else(|| log.idle.clone());
+        let miso_at = |pos: usize| miso.get(pos).copied().unwrap_or(0);
+        let mut mosi = Vec::new();
+
+        for op in operations {
+            match op {
+                Operation::Write(buf) => mosi.extend_from_slice(buf),
+                Operation::Read(buf) => {
+                    for byte in buf.iter_mut() {
+                        *byte = miso_at(mosi.len());
+                        mosi.push(0x00);
+                    }
+                }
+                Operation::Transfer(read, write) => {
+                    let start = mosi.len();
+                    mosi.extend_from_slice(write);
+                    mosi.resize(start + read.len().max(write.len()), 0x00);
+                    for (i, byte) in read.iter_mut().enumerate() {
+                        *byte = miso_at(start + i);
+                    }
+                }
+                Operation::TransferInPlace(buf) => {
+                    for byte in buf.iter_mut() {
+                        mosi.push(*byte);
+                        *byte = miso_at(mosi.len() - 1);
+                    }
+                }
+                Operation::DelayNs(_) => {}
+            }
+        }
+        log.sent.push(mosi);
+        Ok(())
+    }
+}
+
+/// Pin that is always low
+struct Pin;
+
+impl PinErrorType for Pin {
+    type Error = Infallible;
+}
+
+impl OutputPin for Pin {
+    fn set_low(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+}
+
+impl InputPin for Pin {
+    fn is_high(&mut self) -> Result<bool, Infallible> {
+        Ok(false)
+    }
+
+    fn is_low(&mut self) -> Result<bool, Infallible> {
+        Ok(true)
+    }
+}
+
+/// BUSY pin that reads high the given number of times before it goes low
+struct BusyPin(u32);
+
+impl PinErrorType for BusyPin {
+    type Error = Infallible;
+}
+
+impl InputPin for BusyPin {
+    fn is_high(&mut self) -> Result<bool, Infallible> {
+        let high = self.0 > 0;
+        self.0 = self.0.saturating_sub(1);
+        Ok(high)
+    }
+
+    fn is_low(&mut self) -> Result<bool, Infallible> {
+        self.is_high().map(|high| !high)
+    }
+}
+
+type Sx = SX126x<RecordingSpi, Pin, Pin, Pin, Pin, MockDelay>;
+
+fn sx() -> (Sx, RecordingSpi) {
+    let spi = RecordingSpi::default();
+    (SX126x::new(spi.clone(), (Pin, Pin, Pin, Pin), MockDelay), spi)
+}
+
+// 13.1 Operational modes functions
+
+#[test]
+fn set_sleep() {
+    let (mut sx, spi) = sx();
+    sx.sleep(SleepConfig {
+        warm_start: true,
+        rtc_wakeup: true,
+    })
+    .unwrap();
+    assert_eq!(spi.take(), [vec![0x84, 0x05]]);
+
+    // The first NSS edge would only wake the modem, so nothing is sent while asleep
+    assert!(matches!(sx.get_status(), Err(SxError::Asleep)));
+    assert!(matches!(
+        sx.sleep(SleepConfig::default()),
+        Err(SxError::Asleep)
+    ));
+    assert!(spi.take().is_empty());
+
+    sx.wake().unwrap();
+    assert_eq!(spi.take(), [vec![0xC0, 0x00]]);
+    sx.sleep(SleepConfig::default()).unwrap();
+    assert_eq!(spi.take(), [vec![0x84, 0x00]]);
+}
+
+#[test]
+fn busy_is_polled_without_spi_transactions() {
+    let spi = RecordingSpi::default();
+    let mut sx = SX126x::new(spi.clone(), (Pin, BusyPin(3), Pin, Pin), MockDelay);
+    sx.set_standby(StandbyConfig::StbyRc).unwrap();
+    assert_eq!(spi.take(), [vec![0x80, 0x00]]);
+}
+
+#[test]
+fn set_standby() {
+    let (mut sx, spi) = sx();
+    sx.set_standby(StandbyConfig::StbyRc).unwrap();
+    sx.set_standby(StandbyConfig::StbyXOSC).unwrap();
+    assert_eq!(spi.take(), [vec![0x80, 0x00], vec![0x80, 0x01]]);
+}
+
+#[test]
+fn set_fs() {
+    let (mut sx, spi) = sx();
+    spi.respond(&[0x00, 0x42]);
+    let status = sx.set_fs().unwrap();
+    assert_eq!(spi.take(), [vec![0xC1, 0x00]]);
+    assert_eq!(status.chip_mode(), ChipMode::FS);
+}
+
+#[test]
+fn set_tx_returns_status() {
+    let (mut sx, spi) = sx();
+    spi.respond(&[0x00, 0x62]);
+    let status = sx.set_tx(RxTxTimeout::from_ms(100)).unwrap();
+    assert_eq!(spi.take(), [vec![0x83, 0x00, 0x19, 0x00]]);
+    assert_eq!(status.chip_mode(), ChipMode::TX);
+}
+
+#[test]
+fn set_rx_returns_status() {
+    let (mut sx, spi) = sx();
+    spi.respond(&[0x00, 0x52]);
+    let status = sx.set_rx(RxTxTimeout::continuous_rx()).unwrap();
+    assert_eq!(spi.take(), [vec![0x82, 0xFF, 0xFF, 0xFF]]);
+    assert_eq!(status.chip_mode(), ChipMode::RX);
+}
+
+#[test]
+fn set_rx_symbols() {
+    let (mut sx, spi) = sx();
+    sx.set_mod_params(&LoraModParams::default().into()).unwrap();
+    spi.take();
+    // SF7 at 125 kHz: 10 symbols of 1.024 ms, rounded up to 656 RTC steps
+    sx.set_rx_symbols(10).unwrap();
+    assert_eq!(spi.take(), [vec![0x82, 0x00, 0x02, 0x90]]);
+
+    sx.set_mod_params(&GfskModParams::default().into()).unwrap();
+    spi.take();
+    let err = sx.set_rx_symbols(10).unwrap_err();
+    assert!(matches!(
+        err,
+        SxError::InvalidConfig(ConfigError::RxWindowRequiresLoRa)
+    ));
+    assert!(spi.take().is_empty());
+
+    // SF12 at 7.8 kHz: a symbol is 524.288 ms, 33554 RTC steps, so 500
+    // symbols overflow the 24-bit timeout
+    let params = LoraModParams::default()
+        .set_spread_factor(LoRaSpreadFactor::SF12)
+        .set_bandwidth(LoRaBandWidth::BW7);
+    sx.set_mod_params(&params.into()).unwrap();
+    spi.take();
+    let err = sx.set_rx_symbols(500).unwrap_err();
+    assert!(matches!(
+        err,
+        SxError::InvalidConfig(ConfigError::RxWindowTooLong(500))
+    ));
+    assert!(spi.take().is_empty());
+
+    // 0 symbols would disable the timeout
+    let err = sx.set_rx_symbols(0).unwrap_err();
+    assert!(matches!(
+        err,
+        SxError::InvalidConfig(ConfigError::RxWindowEmpty)
+    ));
+    assert!(spi.take().is_empty());
+}
+
+#[test]
+fn stop_timer_on_preamble() {
+    let (mut sx, spi) = sx();
+    sx.stop_timer_on_preamble(false).unwrap();
+    sx.stop_timer_on_preamble(true).unwrap();
+    assert_eq!(spi.take(), [vec![0x9F, 0x00], vec![0x9F, 0x01]]);
+}
+
+#[test]
+fn set_rx_duty_cycle() {
+    let (mut sx, spi) = sx();
+    sx.set_rx_duty_cycle(RxTxTimeout::from_ms(1), RxTxTimeout::from_ms(10))
+        .unwrap();
+    assert_eq!(spi.take(), [vec![0x94, 0x00, 0x00, 0x40, 0x00, 0x02, 0x80]]);
+}
+
+#[test]
+fn set_cad() {
+    let (mut sx, spi) = sx();
+    sx.set_cad_mode().unwrap();
+    assert_eq!(spi.take(), [vec![0xC5, 0x00]]);
+}
+
+#[test]
+fn set_tx_continuous_wave() {
+    let (mut sx, spi) = sx();
+    spi.respond(&[0x00, 0x32]);
+    let test_tx = sx.set_tx_continuous_wave().unwrap();
+    test_tx.stop().unwrap();
+    assert_eq!(
+        spi.take(),
+        [
+            vec![0xC0, 0x00],
+            vec![0xD1, 0x00],
+            vec![0x80, 0x00],
+            vec![0x80, 0x01]
+        ]
+    );
+}
+
+#[test]
+fn set_tx_infinite_preamble() {
+    let (mut sx, spi) = sx();
+    spi.respond(&[0x00, 0x22]);
+    let test_tx = sx.set_tx_infinite_preamble().unwrap();
+    test_tx.stop().unwrap();
+    assert_eq!(
+        spi.take(),
+        [vec![0xC0, 0x00], vec![0xD2, 0x00], vec![0x80, 0x00]]
+    );
+}
+
+#[test]
+fn set_regulator_mode() {
+    let (mut sx, spi) = sx();
+    sx.set_regulator_mode(RegulatorMode::Ldo).unwrap();
+    sx.set_regulator_mode(RegulatorMode::DcDc).unwrap();
+    assert_eq!(spi.take(), [vec![0x96, 0x00], vec![0x96, 0x01]]);
+}
+
+#[test]
+fn calibrate() {
+    let (mut sx, spi) = sx();
+    sx.calibrate(CalibParam::all()).unwrap();
+    assert_eq!(spi.take(), [vec![0x89, 0x7F]]);
+}
+
+#[test]
+fn calibrate_image() {
+    let (mut sx, spi) = sx();
+    // STDBY_RC
+    spi.respond_idle(&[0x00, 0x22]);
+    sx.calibrate_image(CalibImageFreq::MHz863_870).unwrap();
+    sx.calibrate_image(CalibImageFreq::MHz430_440).unwrap();
+    assert_eq!(spi.take(), [vec![0x98, 0xD7, 0xDB], vec![0x98, 0x6B, 0x6F]]);
+}
+
+#[test]
+fn set_pa_config_applies_clamp_fix() {
+    let (mut sx, spi) = sx();
+    let pa_config = PaConfig::default().set_pa_duty_cycle(0x04).set_hp_max(0x07);
+
+    spi.respond(&[]);
+    spi.respond(&[0x00, 0x00, 0x00, 0x00, 0xC8]);
+    sx.set_pa_config(&pa_config).unwrap();
+    assert_eq!(
+        spi.take(),
+        [
+            vec![0x95, 0x04, 0x07, 0x00, 0x01],
+            vec![0x1D, 0x08, 0xD8, 0x00, 0x00],
+            vec![0x0D, 0x08, 0xD8, 0xDE],
+        ]
+    );
+}
+
+#[test]
+fn set_pa_config_removes_clamp_fix() {
+    let (mut sx, spi) = sx();
+    let pa_config = PaConfig::default()
+        .set_pa_duty_cycle(0x01)
+        .set_device_sel(DeviceSel::SX1261)
+        .set_enable_pa_clamp_fix(false);
+
+    spi.respond(&[]);
+    spi.respond(&[0x00, 0x00, 0x00, 0x00, 0xDE]);
+    sx.set_pa_config(&pa_config).unwrap();
+    assert_eq!(
+        spi.take(),
+        [
+            vec![0x95, 0x01, 0x00, 0x01, 0x01],
+            vec![0x1D, 0x08, 0xD8, 0x00, 0x00],
+            vec![0x0D, 0x08, 0xD8, 0xC8],
+        ]
+    );
+}
+
+#[test]
+fn set_rx_tx_fallback_mode() {
+    let (mut sx, spi) = sx();
+    sx.set_rx_tx_fallback_mode(FallbackMode::StdbyRc).unwrap();
+    sx.set_rx_tx_fallback_mode(FallbackMode::StdbyXosc).unwrap();
+    sx.set_rx_tx_fallback_mode(FallbackMode::Fs).unwrap();
+    assert_eq!(
+        spi.take(),
+        [vec![0x93, 0x20], vec![0x93, 0x30], vec![0x93, 0x40]]
+    );
+}
+
+// 13.2 Registers and buffer access
+
+#[test]
+fn write_register() {
+    let (mut sx, spi) = sx();
+    sx.write_register(Register::XtaTrim, &[0x12]).unwrap();
+    assert_eq!(spi.take(), [vec![0x0D, 0x09, 0x11, 0x12]]);
+}
+
+#[test]
+fn read_register() {
+    let (mut sx, spi) = sx();
+    spi.respond(&[0x00, 0x22, 0x22, 0x22, 0x14, 0x24]);
+    let mut result = [0; 2];
+    sx.read_register(Register::LoRaSyncWordMsb, &mut result)
+        .unwrap();
+    assert_eq!(spi.take(), [vec![0x1D, 0x07, 0x40, 0x00, 0x00, 0x00]]);
+    assert_eq!(result, [0x14, 0x24]);
+}
+
+#[test]
+fn read_reg() {
+    let (mut sx, spi) = sx();
+    spi.respond(&[0x00, 0x22, 0x22, 0x22, 0x38]);
+    assert_eq!(sx.read_reg(Register::RxGain).unwrap(), 0x38);
+    assert_eq!(spi.take(), [vec![0x1D, 0x08, 0xAC, 0x00, 0x00]]);
+}
+
+#[test]
+fn read_reg_multi_byte() {
+    let (mut sx, spi) = sx();
+    spi.respond(&[0x00, 0x22, 0x22, 0x22, 0x14, 0x24]);
+    assert_eq!(sx.read_reg_u16(Register::LoRaSyncWordMsb).unwrap(), 0x1424);
+    spi.respond(&[0x00, 0x22, 0x22, 0x22, 0x12, 0x34, 0x56, 0x78]);
+    assert_eq!(
+        sx.read_reg_u32(Register::RandomNumberGen0).unwrap(),
+        0x1234_5678
+    );
+    assert_eq!(
+        spi.take(),
+        [
+            vec![0x1D, 0x07, 0x40, 0x00, 0x00, 0x00],
+            vec![0x1D, 0x08, 0x19, 0x00, 0x00, 0x00, 0x00, 0x00],
+        ]
+    );
+}
+
+#[test]
+fn modify_reg_writes_back_modified_value() {
+    let (mut sx, spi) = sx();
+    spi.respond(&[0x00, 0x22, 0x22, 0x22, 0x94]);
+    spi.respond(&[0x00, 0x2A, 0x2A, 0x2A]);
+    let (status, written) = sx.modify_reg(Register::RxGain, |v| v | 0x02).unwrap();
+    assert_eq!(written, 0x96);
+    // The status of the write is returned, which reports the failure
+    assert!(status.is_command_error());
+    assert_eq!(
+        spi.take(),
+        [
+            vec![0x1D, 0x08, 0xAC, 0x00, 0x00],
+            vec![0x0D, 0x08, 0xAC, 0x96],
+        ]
+    );
+}
+
+#[test]
+fn write_buffer() {
+    let (mut sx, spi) = sx();
+    sx.write_buffer(0x80, b"abc").unwrap();
+    assert_eq!(spi.take(), [vec![0x0E, 0x80, b'a', b'b', b'c']]);
+}
+
+#[test]
+fn read_buffer() {
+    let (mut sx, spi) = sx();
+    spi.respond(&[0x00, 0x22, 0x22, b'x', b'y']);
+    let mut result = [0; 2];
+    sx.read_buffer(0x10, &mut result).unwrap();
+    assert_eq!(spi.take(), [vec![0x1E, 0x10, 0x00, 0x00, 0x00]]);
+    assert_eq!(&result, b"xy");
+}
+
+#[test]
+fn set_sync_word() {
+    let (mut sx, spi) = sx();
+    sx.set_sync_word(0x3444).unwrap();
+    assert_eq!(spi.take(), [vec![0x0D, 0x07, 0x40, 0x34, 0x44]]);
+}
+
+#[test]
+fn set_gfsk_sync_word() {
+    let (mut sx, spi) = sx();
+    sx.set_gfsk_sync_word(&[0xC1, 0x94, 0xC1]).unwrap();
+    assert_eq!(spi.take(), [vec![0x0D, 0x06, 0xC0, 0xC1, 0x94, 0xC1]]);
+
+    let err = sx.set_gfsk_sync_word(&[0; 9]).unwrap_err();
+    assert!(matches!(
+        err,
+        SxError::InvalidConfig(ConfigError::GfskSyncWordLength(9))
+    ));
+    assert!(spi.take().is_empty());
+}
+
+#[test]
+fn set_gfsk_crc() {
+    let (mut sx, spi) = sx();
+    sx.set_gfsk_crc(GfskCrc::IBM).unwrap();
+    assert_eq!(
+        spi.take(),
+        [
+            vec![0x0D, 0x06, 0xBC, 0xFF, 0xFF],
+            vec![0x0D, 0x06, 0xBE, 0x80, 0x05]
+        ]
+    );
+}
+
+#[test]
+fn set_whitening_seed_keeps_reserved_bits() {
+    let (mut sx, spi) = sx();
+    spi.respond(&[0x00, 0x00, 0x00, 0x00, 0xF0]);
+    sx.set_whitening_seed(0x0155).unwrap();
+    assert_eq!(
+        spi.take(),
+        [
+            vec![0x1D, 0x06, 0xB8, 0x00, 0x00],
+            vec![0x0D, 0x06, 0xB8, 0xF1],
+            vec![0x0D, 0x06, 0xB9, 0x55]
+        ]
+    );
+
+    let err = sx.set_whitening_seed(0x0200).unwrap_err();
+    assert!(matches!(
+        err,
+        SxError::InvalidConfig(ConfigError::WhiteningSeedOutOfRange(0x0200))
+    ));
+    assert!(spi.take().is_empty());
+}
+
+#[test]
+fn set_gfsk_addresses() {
+    let (mut sx, spi) = sx();
+    sx.set_gfsk_node_address(0x42).unwrap();
+    sx.set_gfsk_broadcast_address(0xFF).unwrap();
+    assert_eq!(
+        spi.take(),
+        [vec![0x0D, 0x06, 0xCD, 0x42], vec![0x0D, 0x06, 0xCE, 0xFF]]
+    );
+}
+
+#[test]
+fn random_u32() {
+    let (mut sx, spi) = sx();
+    spi.respond(&[]);
+    spi.respond(&[]);
+    spi.respond(&[0x00, 0x22, 0x22, 0x22, 0xDE, 0xAD, 0xBE, 0xEF]);
+    assert_eq!(sx.random_u32().unwrap(), 0xDEAD_BEEF);
+    assert_eq!(
+        spi.take(),
+        [
+            // No IRQs, continuous RX
+            vec![0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+            vec![0x82, 0xFF, 0xFF, 0xFF],
+            vec![0x1D, 0x08, 0x19, 0x00, 0x00, 0x00, 0x00, 0x00],
+            // Back to STDBY_RC with the configured IRQs
+            vec![0x80, 0x00],
+            vec![0x08, 0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00],
+        ]
+    );
+}
+
+#[test]
+fn rng_fills_bytes_from_random_numbers() {
+    let (mut sx, spi) = sx();
+    for random in [[0x01, 0x02, 0x03, 0x04], [0x05, 0x06, 0x07, 0x08]] {
+        spi.respond(&[]);
+        spi.respond(&[]);
+        spi.respond(&[[0x00; 4].as_slice(), &random].concat());
+        spi.respond(&[]);
+        spi.respond(&[]);
+    }
+    let mut dest = [0; 6];
+    sx.rng().fill_bytes(&mut dest);
+    assert_eq!(dest, [0x04, 0x03, 0x02, 0x01, 0x08, 0x07]);
+    assert_eq!(spi.take().len(), 10);
+}
+
+#[test]
+fn fix_sensitivity() {
+    let (mut sx, spi) = sx();
+    // LoRa at 125 kHz: bit 2 is set, the other bits are kept
+    spi.respond(&[0x00, 0x00, 0x01]);
+    spi.respond(&[0x00, 0x00, 0x00, 0x00, 0x81]);
+    sx.fix_sensitivity().unwrap();
+    assert_eq!(
+        spi.take(),
+        [
+            vec![0x11, 0x00, 0x00],
+            vec![0x1D, 0x08, 0x89, 0x00, 0x00],
+            vec![0x0D, 0x08, 0x89, 0x85]
+        ]
+    );
+
+    // LoRa at 500 kHz: bit 2 is cleared
+    let mod_params = LoraModParams::default().set_bandwidth(LoRaBandWidth::BW500);
+    sx.set_mod_params(&mod_params.into()).unwrap();
+    spi.take();
+    spi.respond(&[0x00, 0x00, 0x01]);
+    spi.respond(&[0x00, 0x00, 0x00, 0x00, 0x05]);
+    sx.fix_sensitivity().unwrap();
+    assert_eq!(spi.take()[2], [0x0D, 0x08, 0x89, 0x01]);
+}
+
+// 13.3 DIO and IRQ control functions
+
+#[test]
+fn set_dio_irq_params() {
+    let (mut sx, spi) = sx();
+    let mask = IrqMask::none()
+        .combine(IrqMaskBit::TxDone)
+        .combine(IrqMaskBit::RxDone)
+        .combine(IrqMaskBit::Timeout);
+    sx.set_dio_irq_params(IrqMask::all(), mask, IrqMask::none(), IrqMask::none())
+        .unwrap();
+    assert_eq!(
+        spi.take(),
+        [vec![0x08, 0xFF, 0xFF, 0x02, 0x03, 0x00, 0x00, 0x00, 0x00]]
+    );
+}
+
+#[test]
+fn get_irq_status() {
+    let (mut sx, spi) = sx();
+    spi.respond(&[0x00, 0x22, 0x02, 0x01]);
+    let (status, irq_status) = sx.get_irq_status().unwrap();
+    assert_eq!(spi.take(), [vec![0x12, 0x00, 0x00, 0x00]]);
+    assert_eq!(status.chip_mode(), ChipMode::StbyRC);
+    assert!(irq_status.timeout());
+    assert!(irq_status.tx_done());
+    assert!(!irq_status.rx_done());
+}
+
+#[test]
+fn clear_irq_status() {
+    let (mut sx, spi) = sx();
+    sx.clear_irq_status(IrqMask::none().combine(IrqMaskBit::CadDetected))
+        .unwrap();
+    sx.clear_irq_status(IrqMask::all()).unwrap();
+    assert_eq!(spi.take(), [vec![0x02, 0x01, 0x00], vec![0x02, 0xFF, 0xFF]]);
+}
+
+#[test]
+fn set_dio2_as_rf_switch_ctrl() {
+    let (mut sx, spi) = sx();
+    sx.set_dio2_as_rf_switch_ctrl(true).unwrap();
+    assert_eq!(spi.take(), [vec![0x9D, 0x01]]);
+}
+
+#[test]
+fn set_dio3_as_tcxo_ctrl() {
+    let (mut sx, spi) = sx();
+    sx.set_dio3_as_tcxo_ctrl(TcxoVoltage::Volt1_8, TcxoDelay::from_ms(5))
+        .unwrap();
+    assert_eq!(spi.take(), [vec![0x97, 0x02, 0x00, 0x01, 0x40]]);
+}
+
+// 13.4 RF modulation and packet-related functions
+
+#[test]
+fn set_rf_frequency() {
+    let (mut sx, spi) = sx();
+    // 868 MHz with a 32 MHz XTAL: 868e6 * 2^25 / 32e6
+    sx.set_rf_frequency(Frequency::from_mhz(868)).unwrap();
+    assert_eq!(spi.take(), [vec![0x86, 0x36, 0x40, 0x00, 0x00]]);
+}
+
+#[test]
+fn set_rf_frequency_recalibrates_image() {
+    let (mut sx, spi) = sx();
+    // STDBY_RC
+    spi.respond_idle(&[0x00, 0x22]);
+    sx.calibrate_image(CalibImageFreq::MHz863_870).unwrap();
+    sx.set_rf_frequency(Frequency::from_khz(869_525)).unwrap();
+    spi.take();
+
+    // 433 MHz lies outside the calibrated band
+    sx.set_rf_frequency(Frequency::from_mhz(433)).unwrap();
+    assert_eq!(
+        spi.take(),
+        [vec![0x86, 0x1B, 0x10, 0x00, 0x00], vec![0x98, 0x6B, 0x6F]]
+    );
+
+    sx.set_rf_frequency(Frequency::from_khz(433_175)).unwrap();
+    assert_eq!(spi.take().len(), 1);
+}
+
+#[test]
+fn set_rf_frequency_recalibrates_image_in_stdby_rc() {
+    let (mut sx, spi) = sx();
+    spi.respond_idle(&[0x00, 0x22]);
+    sx.calibrate_image(CalibImageFreq::MHz863_870).unwrap();
+    spi.take();
+
+    // SetRfFrequency reports STDBY_XOSC, the calibration has to run in STDBY_RC
+    spi.respond(&[0x00, 0x32]);
+    sx.set_rf_frequency(Frequency::from_mhz(433)).unwrap();
+    assert_eq!(
+        spi.take(),
+        [
+            vec![0x86, 0x1B, 0x10, 0x00, 0x00],
+            vec![0x80, 0x00],
+            vec![0x98, 0x6B, 0x6F],
+            vec![0x80, 0x01],
+        ]
+    );
+}
+
+#[test]
+fn calibrate_image_outside_stdby_rc_is_not_recorded() {
+    let (mut sx, spi) = sx();
+    // STDBY_XOSC
+    spi.respond(&[0x00, 0x32, 0x32]);
+    let err = sx.calibrate_image(CalibImageFreq::MHz863_870).unwrap_err();
+    assert!(matches!(err, SxError::Command(status) if status.chip_mode() == ChipMode::StbyXOSC));
+
+    // CommandProcessingError in STDBY_RC
+    spi.respond(&[0x00, 0x28, 0x28]);
+    let err = sx.calibrate_image(CalibImageFreq::MHz863_870).unwrap_err();
+    assert!(matches!(err, SxError::Command(status) if status.is_command_error()));
+    spi.take();
+
+    // The band was never calibrated, so nothing is recalibrated
+    spi.respond_idle(&[0x00, 0x22]);
+    sx.set_rf_frequency(Frequency::from_mhz(433)).unwrap();
+    assert_eq!(spi.take().len(), 1);
+}
+
+#[test]
+fn set_packet_type() {
+    let (mut sx, spi) = sx();
+    sx.set_packet_type(PacketType::GFSK).unwrap();
+    sx.set_packet_type(PacketType::LoRa).unwrap();
+    assert_eq!(spi.take(), [vec![0x8A, 0x00], vec![0x8A, 0x01]]);
+}
+
+#[test]
+fn get_packet_type() {
+    let (mut sx, spi) = sx();
+    spi.respond(&[0x00, 0x22, 0x01]);
+    let packet_type = sx.get_packet_type().unwrap();
+    assert_eq!(spi.take(), [vec![0x11, 0x00, 0x00]]);
+    assert!(packet_type == PacketType::LoRa);
+}
+
+#[test]
+fn get_packet_type_rejects_unknown_value() {
+    let (mut sx, spi) = sx();
+    // 0x02 is LR-FHSS on newer silicon
+    spi.respond(&[0x00, 0x22, 0x02]);
+    let err = sx.get_packet_type().unwrap_err();
+    assert!(matches!(
+        err,
+        SxError::Decode(DecodeError::PacketType(0x02))
+    ));
+}
+
+#[test]
+fn set_tx_params() {
+    let (mut sx, spi) = sx();
+    let params = TxParams::default()
+        .set_power_dbm(14)
+        .set_ramp_time(RampTime::Ramp200u);
+    sx.set_tx_params(&params).unwrap();
+    let params = TxParams::default()
+        .set_power_dbm(-9)
+        .set_ramp_time(RampTime::Ramp3400u);
+    sx.set_tx_params(&params).unwrap();
+    assert_eq!(spi.take(), [vec![0x8E, 0x0E, 0x04], vec![0x8E, 0xF7, 0x07]]);
+}
+
+#[test]
+fn set_lora_mod_params() {
+    let (mut sx, spi) = sx();
+    let params = LoraModParams::default()
+        .set_spread_factor(LoRaSpreadFactor::SF12)
+        .set_bandwidth(LoRaBandWidth::BW125)
+        .set_coding_rate(LoraCodingRate::CR4_8)
+        .set_low_dr_opt(true);
+    sx.set_mod_params(&params.into()).unwrap();
+    let params = LoraModParams::default()
+        .set_spread_factor(LoRaSpreadFactor::SF5)
+        .set_bandwidth(LoRaBandWidth::BW41)
+        .set_coding_rate(LoraCodingRate::CR4_6);
+    sx.set_mod_params(&params.into()).unwrap();
+    assert_eq!(
+        spi.take(),
+        [
+            vec![0x8B, 0x0C, 0x04, 0x04, 0x01, 0x00, 0x00, 0x00, 0x00],
+            vec![0x8B, 0x05, 0x0A, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00]
+        ]
+    );
+}
+
+#[test]
+fn set_lora_mod_params_auto_low_dr_opt() {
+    let (mut sx, spi) = sx();
+    let mut ldro = |spread_factor, bandwidth| {
+        let params = LoraModParams::default()
+            .set_spread_factor(spread_factor)
+            .set_bandwidth(bandwidth);
+        sx.set_mod_params(&params.into()).unwrap();
+        spi.take()[0][4]
+    };
+    // Symbol times of 8.192 ms, 16.384 ms, 16.384 ms and 4.096 ms
+    assert_eq!(ldro(LoRaSpreadFactor::SF10, LoRaBandWidth::BW125), 0x00);
+    assert_eq!(ldro(LoRaSpreadFactor::SF11, LoRaBandWidth::BW125), 0x01);
+    assert_eq!(ldro(LoRaSpreadFactor::SF12, LoRaBandWidth::BW250), 0x01);
+    assert_eq!(ldro(LoRaSpreadFactor::SF12, LoRaBandWidth::BW500), 0x00);
+}
+
+#[test]
+fn set_lora_mod_params_rejects_contradicting_low_dr_opt() {
+    let (mut sx, spi) = sx();
+    let params = LoraModParams::default()
+        .set_spread_factor(LoRaSpreadFactor::SF12)
+        .set_bandwidth(LoRaBandWidth::BW125)
+        .set_low_dr_opt(false);
+    assert!(params.low_dr_opt_conflict());
+    let err = sx.set_mod_params(&params.into()).unwrap_err();
+    assert!(matches!(
+        err,
+        SxError::InvalidConfig(ConfigError::LowDataRateOptConflict)
+    ));
+
+    let params = LoraModParams::default().set_low_dr_opt(true);
+    let err = sx.set_mod_params(&params.into()).unwrap_err();
+    assert!(matches!(
+        err,
+        SxError::InvalidConfig(ConfigError::LowDataRateOptConflict)
+    ));
+    assert!(spi.take().is_empty());
+    assert!(!LoraModParams::default().low_dr_opt_conflict());
+
+    // An override is sent as is
+    let params = LoraModParams::default()
+        .set_spread_factor(LoRaSpreadFactor::SF12)
+        .set_bandwidth(LoRaBandWidth::BW125)
+        .override_low_dr_opt(false);
+    assert!(params.low_dr_opt_conflict());
+    sx.set_mod_params(&params.into()).unwrap();
+    let params = LoraModParams::default().override_low_dr_opt(true);
+    sx.set_mod_params(&params.into()).unwrap();
+
+    assert_eq!(
+        spi.take(),
+        [
+            vec![0x8B, 0x0C, 0x04, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00],
+            vec![0x8B, 0x07, 0x04, 0x01, 0x01, 0x00, 0x00, 0x00, 0x00]
+        ]
+    );
+}
+
+#[test]
+fn set_gfsk_mod_params() {
+    let (mut sx, spi) = sx();
+    // br = 32 * 32 MHz / 50 kb/s = 0x005000,
+    // Fdev = 25 kHz * 2^25 / 32 MHz = 0x006666
+    sx.set_mod_params(&GfskModParams::default().into()).unwrap();
+    assert_eq!(
+        spi.take(),
+        [vec![0x8B, 0x00, 0x50, 0x00, 0x09, 0x0B, 0x00, 0x66, 0x66]]
+    );
+}
+
+#[test]
+fn gfsk_mod_params_follow_xtal_freq() {
+    // br = 32 * 30 MHz / 50 kb/s = 0x004B00,
+    // Fdev = 25 kHz * 2^25 / 30 MHz = 0x006D3A
+    let params = GfskModParams::default();
+    let xtal = Frequency::from_mhz(30);
+    assert_eq!(params.bitrate_reg(xtal), 0x00_4B00);
+    assert_eq!(params.freq_deviation_reg(xtal), 0x00_6D3A);
+
+    let (mut sx, spi) = sx();
+    spi.respond_idle(&[0x00, 0x22]);
+    let conf = Config {
+        packet_type: PacketType::GFSK,
+        mod_params: params.into(),
+        xtal_freq: xtal,
+        ..Config::default()
+    };
+    sx.init(conf).unwrap();
+    let sent = spi.take();
+    assert!(sent.contains(&vec![0x8B, 0x00, 0x4B, 0x00, 0x09, 0x0B, 0x00, 0x6D, 0x3A]));
+}
+
+#[test]
+fn set_lora_packet_params() {
+    let (mut sx, spi) = sx();
+    let params = LoRaPacketParams::default()
+        .set_preamble_len(0x010C)
+        .set_header_type(LoRaHeaderType::FixedLen)
+        .set_payload_len(0x40)
+        .set_crc_type(LoRaCrcType::CrcOn)
+        .set_invert_iq(LoRaInvertIq::Inverted);
+    sx.set_packet_params(&params.into()).unwrap();
+    sx.set_packet_params(&PacketParams::default()).unwrap();
+    assert_eq!(
+        spi.take(),
+        [
+            vec![0x8C, 0x01, 0x0C, 0x01, 0x40, 0x01, 0x01, 0x00, 0x00, 0x00],
+            vec![0x8C, 0x00, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]
+        ]
+    );
+}
+
+#[test]
+fn set_gfsk_packet_params() {
+    let (mut sx, spi) = sx();
+    let params = GfskPacketParams::default()
+        .set_preamble_len(0x0020)
+        .set_preamble_detector(GfskPreambleDetector::Bits16)
+        .set_sync_word_len(0x18)
+        .set_addr_comp(GfskAddrComp::NodeAndBroadcast)
+        .set_packet_length(GfskPacketLength::Variable)
+        .set_payload_len(0xFF)
+        .set_crc_type(GfskCrcType::Crc2BytesInv)
+        .set_whitening(GfskWhitening::On);
+    sx.set_packet_params(&params.into()).unwrap();
+    sx.set_packet_params(&GfskPacketParams::default().into())
+        .unwrap();
+    assert_eq!(
+        spi.take(),
+        [
+            vec![0x8C, 0x00, 0x20, 0x05, 0x18, 0x02, 0x01, 0xFF, 0x06, 0x01],
+            vec![0x8C, 0x00, 0x20, 0x05, 0x10, 0x00, 0x01, 0x00, 0x01, 0x00]
+        ]
+    );
+}
+
+#[test]
+fn set_cad_params() {
+    let (mut sx, spi) = sx();
+    sx.set_cad_config(CadParams {
+        symbol_num: CadSymbolNum::CAD_ON_4_SYMB,
+        det_peak: CadDetPeak::new(22).unwrap(),
+        det_min: CadDetMin::new(10).unwrap(),
+        exit_mode: CadExit::CAD_RX,
+        timeout: Some(CadTimeout::new(0x01_2345).unwrap()),
+    })
+    .unwrap();
+    sx.set_cad_config(CadParams {
+        symbol_num: CadSymbolNum::CAD_ON_16_SYMB,
+        det_peak: CadDetPeak::new(25).unwrap(),
+        det_min: CadDetMin::new(10).unwrap(),
+        exit_mode: CadExit::CAD_ONLY,
+        timeout: None,
+    })
+    .unwrap();
+    assert_eq!(
+        spi.take(),
+        [
+            vec![0x88, 0x02, 22, 10, 0x01, 0x01, 0x23, 0x45],
+            vec![0x88, 0x04, 25, 10, 0x00, 0x00, 0x00, 0x00]
+        ]
+    );
+}
+
+#[test]
+fn set_buffer_base_address() {
+    let (mut sx, spi) = sx();
+    sx.set_buffer_base_address(0x00, 0x80).unwrap();
+    assert_eq!(spi.take(), [vec![0x8F, 0x00, 0x80]]);
+}
+
+#[test]
+fn set_lora_symb_num_timeout() {
+    let (mut sx, spi) = sx();
+    sx.set_lora_symb_num_timeout(0).unwrap();
+    sx.set_lora_symb_num_timeout(8).unwrap();
+    // 100 symbols: mantissa 13, exponent 1
+    sx.set_lora_symb_num_timeout(100).unwrap();
+    // 248 symbols: mantissa 31, exponent 1
+    sx.set_lora_symb_num_timeout(LORA_SYMB_NUM_TIMEOUT_MAX)
+        .unwrap();
+    assert_eq!(
+        spi.take(),
+        [
+            vec![0xA0, 0x00],
+            vec![0xA0, 0x08],
+            vec![0x0D, 0x07, 0x06, 0x20],
+            vec![0xA0, 0x68],
+            vec![0x0D, 0x07, 0x06, 0x69],
+            vec![0xA0, 0xF8],
+            vec![0x0D, 0x07, 0x06, 0xF9]
+        ]
+    );
+
+    let err = sx.set_lora_symb_num_timeout(249).unwrap_err();
+    assert!(matches!(
+        err,
+        SxError::InvalidConfig(ConfigError::SymbNumTimeoutOutOfRange(249))
+    ));
+    assert!(spi.take().is_empty());
+}
+
+#[test]
+fn lora_symb_num_timeout_rounds_up() {
+    let symbols = |symb_num| LoRaSymbNumTimeout::new(symb_num).unwrap().symbols();
+    assert_eq!(symbols(0), 0);
+    assert_eq!(symbols(7), 8);
+    assert_eq!(symbols(62), 62);
+    assert_eq!(symbols(63), 64);
+    assert_eq!(symbols(100), 104);
+    assert_eq!(symbols(LORA_SYMB_NUM_TIMEOUT_MAX), 248);
+    assert!(LoRaSymbNumTimeout::new(249).is_none());
+}
+
+// 13.5 Communication status information
+
+#[test]
+fn get_status() {
+    let (mut sx, spi) = sx();
+    spi.respond(&[0x00, 0x2A]);
+    let status = sx.get_status().unwrap();
+    assert_eq!(spi.take(), [vec![0xC0, 0x00]]);
+    assert_eq!(status.chip_mode(), ChipMode::StbyRC);
+    assert!(matches!(
+        status.command_status(),
+        Some(CommandStatus::FailureToExecute)
+    ));
+}
+
+#[test]
+fn status_reports_every_chip_mode() {
+    assert_eq!(Status::from(0x00).chip_mode(), ChipMode::Sleep);
+    assert_eq!(Status::from(0x40).chip_mode(), ChipMode::FS);
+    assert_eq!(Status::from(0x12).chip_mode(), ChipMode::Unknown(0x1));
+    assert_eq!(Status::from(0x72).chip_mode(), ChipMode::Unknown(0x7));
+}
+
+#[test]
+fn decoders_reject_invalid_values() {
+    assert!(matches!(
+        LoRaSpreadFactor::try_from(0x0C),
+        Ok(LoRaSpreadFactor::SF12)
+    ));
+    assert!(matches!(
+        LoRaSpreadFactor::try_from(0x0D),
+        Err(DecodeError::SpreadFactor(0x0D))
+    ));
+    assert!(matches!(
+        LoRaBandWidth::try_from(0x0A),
+        Ok(LoRaBandWidth::BW41)
+    ));
+    assert!(matches!(
+        LoRaBandWidth::try_from(0x07),
+        Err(DecodeError::Bandwidth(0x07))
+    ));
+    assert!(matches!(
+        LoraCodingRate::try_from(0x04),
+        Ok(LoraCodingRate::CR4_8)
+    ));
+    assert!(matches!(
+        LoraCodingRate::try_from(0x00),
+        Err(DecodeError::CodingRate(0x00))
+    ));
+    assert!(matches!(PacketType::try_from(0x00), Ok(PacketType::GFSK)));
+    assert!(matches!(
+        PacketType::try_from(0x02),
+        Err(DecodeError::PacketType(0x02))
+    ));
+}
+
+#[test]
+fn commands_return_status() {
+    let (mut sx, spi) = sx();
+    // STDBY_XOSC, no error
+    spi.respond(&[0x00, 0x30]);
+    let status = sx.set_standby(StandbyConfig::StbyXOSC).unwrap();
+    assert_eq!(status.chip_mode(), ChipMode::StbyXOSC);
+
+    // Command errors are returned, but not turned into an SxError by default
+    spi.respond(&[0x00, 0x28, 0x28]);
+    let status = sx.set_packet_type(PacketType::LoRa).unwrap();
+    assert!(status.is_command_error());
+    spi.respond(&[0x00, 0x2A, 0x2A, 0x2A]);
+    let status = sx.write_register(Register::XtaTrim, &[0x12]).unwrap();
+    assert!(status.is_command_error());
+}
+
+#[test]
+fn check_status_turns_command_errors_into_errors() {
+    let (mut sx, spi) = sx();
+    sx.set_check_status(true);
+
+    spi.respond(&[0x00, 0x22, 0x22]);
+    sx.set_packet_type(PacketType::GFSK).unwrap();
+
+    // CommandProcessingError
+    spi.respond(&[0x00, 0x28, 0x28]);
+    let err = sx.set_packet_type(PacketType::LoRa).unwrap_err();
+    assert!(matches!(
+        err,
+        SxError::Command(status) if matches!(
+            status.command_status(),
+            Some(CommandStatus::CommandProcessingError)
+        )
+    ));
+
+    // FailureToExecute, from a register write and a composite command
+    spi.respond(&[0x00, 0x2A, 0x2A, 0x2A]);
+    let err = sx.write_register(Register::XtaTrim, &[0x12]).unwrap_err();
+    assert!(matches!(err, SxError::Command(_)));
+    spi.respond(&[0x00, 0x2A, 0x2A, 0x2A]);
+    let err = sx.set_sync_word(0x3444).unwrap_err();
+    assert!(matches!(err, SxError::Command(_)));
+
+    // TX done and data available are not errors
+    spi.respond(&[0x00, 0x2C, 0x2C, 0x2C]);
+    sx.set_tx(RxTxTimeout::from_ms(100)).unwrap();
+    spi.respond(&[0x00, 0x24, 0x24]);
+    sx.set_buffer_base_address(0x00, 0x80).unwrap();
+}
+
+#[test]
+fn check_status_covers_reads_and_opcode_only_commands() {
+    let (mut sx, spi) = sx();
+    sx.set_check_status(true);
+    let is_command_error = |err| matches!(err, SxError::Command(_));
+
+    spi.respond(&[0x00, 0x2A, 0x2A, 0x2A, 0x2A]);
+    let err = sx.read_register(Register::RxGain, &mut [0]).unwrap_err();
+    assert!(is_command_error(err));
+    spi.respond(&[0x00, 0x2A, 0x2A, 0x2A]);
+    let err = sx.read_buffer(0x00, &mut [0]).unwrap_err();
+    assert!(is_command_error(err));
+    spi.respond(&[0x00, 0x2A, 0x00, 0x00]);
+    let err = sx.get_irq_status().unwrap_err();
+    assert!(is_command_error(err));
+    spi.respond(&[0x00, 0x2A, 0x00, 0x00, 0x00]);
+    let err = sx.get_packet_status().unwrap_err();
+    assert!(is_command_error(err));
+    spi.respond(&[0x00, 0x2A, 0x00, 0x00, 0x00]);
+    let err = sx.get_gfsk_packet_status().unwrap_err();
+    assert!(is_command_error(err));
+    spi.respond(&[0x00, 0x28]);
+    let err = sx.set_fs().unwrap_err();
+    assert!(is_command_error(err));
+    spi.respond(&[0x00, 0x28]);
+    let err = sx.set_cad_mode().unwrap_err();
+    assert!(is_command_error(err));
+    spi.respond(&[0x00, 0x22]);
+    spi.respond(&[0x00, 0x28]);
+    assert!(sx.set_tx_continuous_wave().is_err());
+}
+
+#[test]
+fn composite_commands_return_first_command_error() {
+    let (mut sx, spi) = sx();
+    // The first register write fails, the second one succeeds
+    spi.respond(&[0x00, 0x2A, 0x2A, 0x2A, 0x2A]);
+    let status = sx.set_gfsk_crc(GfskCrc::IBM).unwrap();
+    assert!(status.is_command_error());
+
+    // SetPaConfig succeeds, the register write applying the clamping fix fails
+    spi.respond(&[0x00, 0x22, 0x22, 0x22, 0x22]);
+    spi.respond(&[0x00, 0x22, 0x22, 0x22, 0x00]);
+    spi.respond(&[0x00, 0x2A, 0x2A, 0x2A]);
+    let status = sx.set_pa_config(&PaConfig::default()).unwrap();
+    assert!(status.is_command_error());
+
+    // The read of the sensitivity fix succeeds, writing it back fails
+    spi.respond(&[0x00, 0x22, 0x01]);
+    spi.respond(&[0x00, 0x22, 0x22, 0x22, 0x00]);
+    spi.respond(&[0x00, 0x2A, 0x2A, 0x2A]);
+    let status = sx.fix_sensitivity().unwrap();
+    assert!(status.is_command_error());
+
+    // Writing the whitening seed MSB fails, the LSB write succeeds
+    spi.respond(&[0x00, 0x22, 0x22, 0x22, 0x00]);
+    spi.respond(&[0x00, 0x2A, 0x2A, 0x2A]);
+    let status = sx.set_whitening_seed(0x0100).unwrap();
+    assert!(status.is_command_error());
+
+    spi.respond(&[0x00, 0x22, 0x22]);
+    let status = sx.set_sync_word(0x1424).unwrap();
+    assert_eq!(status.chip_mode(), ChipMode::StbyRC);
+    assert!(!status.is_command_error());
+    spi.take();
+}
+
+#[test]
+fn get_rx_buffer_status() {
+    let (mut sx, spi) = sx();
+    spi.respond(&[0x00, 0x22, 0x20, 0x80]);
+    let status = sx.get_rx_buffer_status().unwrap();
+    assert_eq!(spi.take(), [vec![0x13, 0x00, 0x00, 0x00]]);
+    assert_eq!(status.payload_length_rx(), 0x20);
+    assert_eq!(status.rx_start_buffer_pointer(), 0x80);
+}
+
+#[test]
+fn get_packet_status() {
+    let (mut sx, spi) = sx();
+    spi.respond(&[0x00, 0x22, 0x40, 0xF8, 0x50]);
+    let (status, packet_status) = sx.get_packet_status().unwrap();
+    assert_eq!(spi.take(), [vec![0x14, 0x00, 0x00, 0x00, 0x00]]);
+    assert_eq!(status.chip_mode(), ChipMode::StbyRC);
+    let status = packet_status;
+    assert_eq!(status.rssi_pkt(), -32.0);
+    assert_eq!(status.snr_pkt(), -2.0);
+    assert_eq!(status.signal_rssi_pkt(), -40.0);
+}
+
+#[test]
+fn get_gfsk_packet_status() {
+    let (mut sx, spi) = sx();
+    spi.respond(&[0x00, 0x22, 0x22, 0x40, 0x50]);
+    let (status, packet_status) = sx.get_gfsk_packet_status().unwrap();
+    assert_eq!(spi.take(), [vec![0x14, 0x00, 0x00, 0x00, 0x00]]);
+    assert_eq!(status.chip_mode(), ChipMode::StbyRC);
+    let status = packet_status;
+    assert!(status.rx_status().address_err());
+    assert!(status.rx_status().pkt_received());
+    assert!(!status.rx_status().crc_err());
+    assert_eq!(status.rssi_sync(), -32.0);
+    assert_eq!(status.rssi_avg(), -40.0);
+}
+
+#[test]
+fn get_stats() {
+    let (mut sx, spi) = sx();
+    spi.respond(&[0x00, 0x22, 0x00, 0x05, 0x00, 0x02, 0x01, 0x00]);
+    let stats = sx.get_stats().unwrap();
+    assert_eq!(
+        spi.take(),
+        [vec![0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]]
+    );
+    assert_eq!(stats.rx_pkt, 5);
+    assert_eq!(stats.crc_error, 2);
+    assert_eq!(stats.header_error, 0x0100);
+}
+
+// 13.6 Miscellaneous
+
+#[test]
+fn get_device_errors() {
+    let (mut sx, spi) = sx();
+    // OpError is sent MSB first
+    spi.respond(&[0x00, 0x22, 0x01, 0x20]);
+    let errors = sx.get_device_errors().unwrap();
+    assert_eq!(spi.take(), [vec![0x17, 0x00, 0x00, 0x00]]);
+    assert!(errors.pa_ramp_err());
+    assert!(errors.xosc_start_err());
+    assert!(!errors.rc64k_calib_err());
+    assert!(!errors.pll_lock_err());
+}
+
+#[test]
+fn clear_device_errors() {
+    let (mut sx, spi) = sx();
+    sx.clear_device_errors().unwrap();
+    assert_eq!(spi.take(), [vec![0x07, 0x00, 0x00]]);
+}
+
+// Parameter conversions
+
+#[test]
+fn timeout_from_ms() {
+    // 1 ms is 64 steps of 15.625 μs, sent MSB first
+    assert_eq!(<[u8; 3]>::from(RxTxTimeout::from_ms(1)), [0x00, 0x00, 0x40]);
+    assert_eq!(
+        <[u8; 3]>::from(RxTxTimeout::from_ms(100)),
+        [0x00, 0x19, 0x00]
+    );
+    assert_eq!(
+        <[u8; 3]>::from(RxTxTimeout::from_ms(262_143)),
+        [0xFF, 0xFF, 0xC0]
+    );
+    assert_eq!(
+        <[u8; 3]>::from(RxTxTimeout::from(0x12_3456)),
+        [0x12, 0x34, 0x56]
+    );
+    assert_eq!(
+        <[u8; 3]>::from(RxTxTimeout::continuous_rx()),
+        [0xFF, 0xFF, 0xFF]
+    );
+}
+
+#[test]
+fn tcxo_delay_from_ms() {
+    assert_eq!(<[u8; 3]>::from(TcxoDelay::from_ms(1)), [0x00, 0x00, 0x40]);
+    assert_eq!(<[u8; 3]>::from(TcxoDelay::from_ms(5)), [0x00, 0x01, 0x40]);
+    assert_eq!(
+        <[u8; 3]>::from(TcxoDelay::from_ms(1000)),
+        [0x00, 0xFA, 0x00]
+    );
+}
+
+#[test]
+fn timeout_from_u32_uses_low_24_bits() {
+    // Taking the top three bytes of the big-endian value turned small
+    // timeouts into zero, that is no timeout at all
+    assert_eq!(<[u8; 3]>::from(RxTxTimeout::from(1)), [0x00, 0x00, 0x01]);
+    assert_eq!(<[u8; 3]>::from(RxTxTimeout::from(0x40)), [0x00, 0x00, 0x40]);
+    assert_eq!(
+        <[u8; 3]>::from(RxTxTimeout::from(0xFF_FFFF)),
+        [0xFF, 0xFF, 0xFF]
+    );
+    let (mut sx, spi) = sx();
+    sx.set_tx(RxTxTimeout::from(0x01_0203)).unwrap();
+    assert_eq!(spi.take(), [vec![0x83, 0x01, 0x02, 0x03]]);
+}
+
+#[test]
+fn timeout_to_us() {
+    assert_eq!(RxTxTimeout::from_ms(10).to_us(), Some(10_000));
+    assert_eq!(RxTxTimeout::from(3).to_us(), Some(47));
+    assert_eq!(RxTxTimeout::from(0).to_us(), None);
+    assert_eq!(RxTxTimeout::continuous_rx().to_us(), None);
+}
+
+#[test]
+fn cad_timeout_split_u24() {
+    assert_eq!(CadTimeout::split_u24(0x12_3456), (0x12, 0x34, 0x56));
+    assert_eq!(CadTimeout::split_u24(0x00_0001), (0x00, 0x00, 0x01));
+    assert!(CadTimeout::new(CadTimeout::MIN).is_ok());
+    assert!(CadTimeout::new(0xFF_FFFF).is_ok());
+    assert_eq!(
+        CadTimeout::new(0x100_0000).unwrap_err(),
+        ConfigError::CadTimeoutOutOfRange(0x100_0000)
+    );
+}
+
+#[test]
+fn cad_params_out_of_range() {
+    assert_eq!(
+        CadDetPeak::new(17).unwrap_err(),
+        ConfigError::CadDetPeakOutOfRange(17)
+    );
+    assert_eq!(
+        CadDetPeak::new(36).unwrap_err(),
+        ConfigError::CadDetPeakOutOfRange(36)
+    );
+    assert_eq!(
+        CadDetMin::new(11).unwrap_err(),
+        ConfigError::CadDetMinOutOfRange(11)
+    );
+}
+
+#[test]
+fn calib_param() {
+    let bits = |param: CalibParam| u8::from(param);
+    assert_eq!(bits(CalibParam::all()), 0x7F);
+    assert_eq!(
+        bits(CalibParam::new(
+            true, false, false, false, false, false, false
+        )),
+        0x01
+    );
+    assert_eq!(
+        bits(CalibParam::new(
+            false, false, true, false, false, false, false
+        )),
+        0x04
+    );
+    assert_eq!(
+        bits(CalibParam::new(
+            false, false, false, false, false, false, true
+        )),
+        0x40
+    );
+    // Bit 7 is RFU
+    assert_eq!(bits(CalibParam::from(0xFF)), 0x7F);
+}
+
+#[test]
+fn calib_image_freq() {
+    let bytes = |hz| <[u8; 2]>::from(CalibImageFreq::from_rf_frequency(Frequency::from_hz(hz)));
+    assert_eq!(bytes(433_000_000), [0x6B, 0x6F]);
+    assert_eq!(bytes(490_000_000), [0x75, 0x81]);
+    assert_eq!(bytes(780_000_000), [0xC1, 0xC5]);
+    assert_eq!(bytes(868_000_000), [0xD7, 0xDB]);
+    assert_eq!(bytes(915_000_000), [0xE1, 0xE9]);
+    // Outside the table: the 4 MHz steps around the frequency
+    assert_eq!(bytes(169_400_000), [0x2A, 0x2B]);
+    assert_eq!(bytes(160_000_000), [0x28, 0x29]);
+}
+
+#[test]
+fn calib_image_freq_from_range() {
+    let range = CalibImageFreq::from_range(Frequency::from_mhz(920), Frequency::from_mhz(925));
+    assert_eq!(<[u8; 2]>::from(range), [0xE6, 0xE8]);
+    assert!(range.contains(Frequency::from_mhz(920)));
+    assert!(range.contains(Frequency::from_khz(923_200)));
+    assert!(range.contains(Frequency::from_mhz(928)));
+    assert!(!range.contains(Frequency::from_khz(919_900)));
+    assert!(!range.contains(Frequency::from_khz(928_100)));
+}
+
+#[test]
+fn frequency_pll_steps() {
+    let xtal = Frequency::XTAL_32MHZ;
+    assert_eq!(Frequency::from_mhz(868).to_pll_steps(xtal), 0x3640_0000);
+    assert_eq!(Frequency::from_mhz(915).to_pll_steps(xtal), 959_447_040);
+    // 949_170_995.2 steps, rounded to the nearest
+    assert_eq!(Frequency::from_khz(905_200).to_pll_steps(xtal), 949_170_995);
+    assert_eq!(
+        Frequency::from_pll_steps(949_170_995, xtal),
+        Frequency::from_khz(905_200)
+    );
+    assert_eq!(
+        Frequency::from_mhz(868).to_pll_steps(Frequency::from_mhz(26)),
+        1_120_201_807
+    );
+}
+
+#[test]
+fn sleep_config() {
+    let byte = |warm_start, rtc_wakeup| {
+        u8::from(SleepConfig {
+            warm_start,
+            rtc_wakeup,
+        })
+    };
+    assert_eq!(byte(false, false), 0x00);
+    assert_eq!(byte(false, true), 0x01);
+    assert_eq!(byte(true, false), 0x04);
+}
+
+#[test]
+fn status() {
+    let status = Status::from(0x5C);
+    assert_eq!(status.chip_mode(), ChipMode::RX);
+    assert!(matches!(
+        status.command_status(),
+        Some(CommandStatus::CommandTxDone)
+    ));
+    assert!(Status::from(0x28).is_command_error());
+    assert!(!Status::from(0x24).is_command_error());
+}
@@ -0,0 +1,71 @@
+use std::convert::Infallible;
+use std::error::Error;
+
+use sx126x::op::*;
+use sx126x::{PinError, RxError, SpiError, SxError};
+
+type TestError = SxError<Infallible, Infallible>;
+
+#[test]
+fn config_error_display() {
+    assert_eq!(
+        ConfigError::CadDetPeakOutOfRange(40).to_string(),
+        "cadDetPeak 40 is outside 18 to 35"
+    );
+    assert_eq!(
+        ConfigError::GfskSyncWordLength(9).to_string(),
+        "GFSK sync word of 9 bytes, must be 1 to 8 bytes long"
+    );
+    assert_eq!(
+        ConfigError::WhiteningSeedOutOfRange(0x200).to_string(),
+        "whitening seed 0x200 does not fit in 9 bits"
+    );
+    assert_eq!(
+        ConfigError::SymbNumTimeoutOutOfRange(249).to_string(),
+        "LoRa symbol number timeout 249 exceeds 248"
+    );
+}
+
+#[test]
+fn sx_error_display() {
+    let err: TestError = ConfigError::CadTimeoutOutOfRange(0x100_0000).into();
+    assert_eq!(
+        err.to_string(),
+        "invalid configuration: cadTimeout 0x1000000 does not fit in 24 bits"
+    );
+    let err: TestError = RxError::BufferTooSmall(64).into();
+    assert_eq!(
+        err.to_string(),
+        "reception failed: received packet of 64 bytes does not fit in the buffer"
+    );
+    let err: TestError = SxError::Command(Status::from(0x2A));
+    assert_eq!(err.to_string(), "command failed: Some(FailureToExecute)");
+    let err: TestError = DeviceErrors::from(0x0040).into();
+    assert!(err.to_string().starts_with("device errors: DeviceErrors {"));
+    let err: TestError = DecodeError::SpreadFactor(0x0D).into();
+    assert_eq!(
+        err.to_string(),
+        "invalid response: invalid LoRa spreading factor 0x0d"
+    );
+    assert_eq!(
+        TestError::Timeout.to_string(),
+        "timed out waiting on the modem"
+    );
+    assert_eq!(TestError::Asleep.to_string(), "the modem is asleep");
+}
+
+#[test]
+fn sx_error_source() {
+    let err: TestError = ConfigError::WhiteningSeedOutOfRange(0x200).into();
+    let source = err.source().unwrap();
+    assert_eq!(
+        source.to_string(),
+        ConfigError::WhiteningSeedOutOfRange(0x200).to_string()
+    );
+
+    let err: SxError<u8, u8> = SpiError::Transfer(7).into();
+    assert!(err.source().is_none());
+    assert_eq!(err.to_string(), "SPI transfer failed: 7");
+    let err: SxError<u8, u8> = PinError::Input(3).into();
+    assert_eq!(err.to_string(), "reading input pin failed: 3");
+}
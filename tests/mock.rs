@@ -0,0 +1,566 @@
+use embassy_futures::block_on;
+use embedded_hal::digital::{InputPin, OutputPin};
+use embedded_hal::spi::{Operation, SpiDevice};
+use embedded_hal_async::digital::Wait;
+use sx126x::conf::Config;
+use sx126x::mock::{MockChip, MockDelay, MockError, Mode};
+use sx126x::op::*;
+use sx126x::reg::Register;
+use sx126x::{RxError, SX126x, SX126xAsync, SxError};
+
+fn packet_params(payload_len: u8) -> LoRaPacketParams {
+    LoRaPacketParams::default()
+        .set_payload_len(payload_len)
+        .set_crc_type(LoRaCrcType::CrcOn)
+}
+
+#[test]
+fn init_async_configures_modem() {
+    let chip = MockChip::new();
+    let mut sx = SX126x::new(chip.spi(), chip.pins(), MockDelay);
+
+    block_on(sx.init_async(Config::default())).unwrap();
+
+    assert_eq!(chip.mode(), Mode::StbyRc);
+    assert_eq!(chip.packet_type(), Some(PacketType::LoRa));
+    assert_eq!(chip.register(Register::LoRaSyncWordMsb), 0x00);
+    assert_eq!(chip.register(Register::LoRaSyncWordLsb), 0x12);
+    assert!(!chip.busy());
+}
+
+#[test]
+fn write_bytes_async_transmits_payload() {
+    let chip = MockChip::new();
+    let mut sx = SX126x::new(chip.spi(), chip.pins(), MockDelay);
+    block_on(sx.init_async(Config::default())).unwrap();
+
+    let outcome =
+        block_on(sx.write_bytes_async(b"hello", RxTxTimeout::from_ms(100), packet_params(5)))
+            .unwrap();
+
+    assert!(matches!(outcome, TxOutcome::Done));
+    assert_eq!(chip.transmitted(), vec![b"hello".to_vec()]);
+    assert!(!chip.dio1());
+}
+
+#[test]
+fn read_bytes_async_receives_queued_packet() {
+    let chip = MockChip::new();
+    let mut sx = SX126x::new(chip.spi(), chip.pins(), MockDelay);
+    block_on(sx.init_async(Config::default())).unwrap();
+    chip.queue_rx(b"ping");
+
+    let mut buf = [0; 16];
+    let packet = block_on(sx.read_bytes_async(&mut buf, RxTxTimeout::from_ms(100))).unwrap();
+    assert_eq!(&buf[..packet.len], b"ping");
+
+    let err = block_on(sx.read_bytes_async(&mut buf, RxTxTimeout::from_ms(100))).unwrap_err();
+    assert!(matches!(err, SxError::Rx(RxError::Timeout)));
+}
+
+#[test]
+fn read_bytes_async_ends_on_header_error_and_restores_irq_masks() {
+    let chip = MockChip::new();
+    let mut sx = SX126x::new(chip.spi(), chip.pins(), MockDelay);
+    let conf = Config {
+        dio1_irq_mask: IrqMask::none()
+            .combine(IrqMaskBit::RxDone)
+            .combine(IrqMaskBit::Timeout),
+        ..Config::default()
+    };
+    block_on(sx.init_async(conf)).unwrap();
+
+    // HeaderError is routed to DIO1 during RX, otherwise the wait would never end
+    chip.queue_rx_header_error();
+    let mut buf = [0; 16];
+    let err = block_on(sx.read_bytes_async(&mut buf, RxTxTimeout::from_ms(100))).unwrap_err();
+    assert!(matches!(err, SxError::Rx(RxError::HeaderError)));
+
+    // Once the reception ended, DIO1 only follows the masks of the Config again
+    chip.raise_irq(IrqMaskBit::HeaderError);
+    assert!(!chip.dio1());
+    chip.raise_irq(IrqMaskBit::Timeout);
+    assert!(chip.dio1());
+}
+
+#[test]
+fn read_bytes_restores_irq_masks_when_dio1_times_out() {
+    let chip = MockChip::new();
+    let mut sx = SX126x::new(chip.spi(), chip.pins(), MockDelay);
+    let conf = Config {
+        dio1_irq_mask: IrqMask::none().combine(IrqMaskBit::RxDone),
+        ..Config::default()
+    };
+    sx.init(conf).unwrap();
+
+    // The RX timeout is not routed to DIO1, so the driver gives up on DIO1 itself
+    let mut buf = [0; 16];
+    let err = sx.read_bytes(&mut buf, RxTxTimeout::from_ms(1)).unwrap_err();
+    assert!(matches!(err, SxError::Timeout));
+
+    sx.clear_irq_status(IrqMask::all()).unwrap();
+    chip.raise_irq(IrqMaskBit::HeaderError);
+    assert!(!chip.dio1());
+}
+
+#[test]
+fn cold_start_is_reconfigured_on_wake() {
+    let chip = MockChip::new();
+    let mut sx = SX126x::new(chip.spi(), chip.pins(), MockDelay);
+    block_on(sx.init_async(Config::default())).unwrap();
+
+    sx.sleep(SleepConfig::default()).unwrap();
+    assert_eq!(chip.mode(), Mode::Sleep);
+
+    block_on(sx.wake_async()).unwrap();
+    assert_eq!(chip.packet_type(), Some(PacketType::LoRa));
+    assert_eq!(chip.register(Register::LoRaSyncWordLsb), 0x12);
+}
+
+#[test]
+fn command_while_busy_is_rejected() {
+    let chip = MockChip::new();
+    let mut sx = SX126x::new(chip.spi(), chip.pins(), MockDelay);
+    block_on(sx.init_async(Config::default())).unwrap();
+
+    sx.calibrate(CalibParam::from(0x7F)).unwrap();
+    // Bypass the driver, which waits on BUSY before every command
+    let err = chip.spi().transfer_in_place(&mut [0x80, 0x01]).unwrap_err();
+    assert!(matches!(err, MockError::Busy { opcode: 0x80 }));
+
+    sx.set_standby(StandbyConfig::StbyXOSC).unwrap();
+    assert_eq!(chip.mode(), Mode::StbyXosc);
+}
+
+#[test]
+fn set_rf_frequency_recalibrates_image_in_stdby_rc() {
+    let chip = MockChip::new();
+    let mut sx = SX126x::new(chip.spi(), chip.pins(), MockDelay);
+    block_on(sx.init_async(Config::default())).unwrap();
+
+    // The mock rejects a CalibrateImage sent while BUSY is high or outside STDBY_RC
+    sx.set_standby(StandbyConfig::StbyXOSC).unwrap();
+    sx.set_rf_frequency(Frequency::from_mhz(433)).unwrap();
+    assert_eq!(chip.mode(), Mode::StbyXosc);
+
+    let chip = MockChip::new();
+    let mut sx = SX126xAsync::new(chip.spi(), chip.pins());
+    block_on(sx.init(Config::default())).unwrap();
+
+    block_on(sx.set_fs()).unwrap();
+    block_on(sx.set_rf_frequency(Frequency::from_mhz(433))).unwrap();
+    assert_eq!(chip.mode(), Mode::Fs);
+}
+
+#[test]
+fn blocking_init_and_write_bytes() {
+    let chip = MockChip::new();
+    let mut sx = SX126x::new(chip.spi(), chip.pins(), MockDelay);
+
+    sx.init(Config::default()).unwrap();
+    let outcome = sx
+        .write_bytes(b"hi", RxTxTimeout::from_ms(100), packet_params(2))
+        .unwrap();
+
+    assert!(matches!(outcome, TxOutcome::Done));
+    assert_eq!(chip.transmitted(), vec![b"hi".to_vec()]);
+}
+
+#[test]
+fn async_spi_driver_init_and_write_bytes() {
+    let chip = MockChip::new();
+    let mut sx = SX126xAsync::new(chip.spi(), chip.pins());
+
+    block_on(sx.init(Config::default())).unwrap();
+    let outcome =
+        block_on(sx.write_bytes(b"async", RxTxTimeout::from_ms(100), packet_params(5))).unwrap();
+
+    assert!(matches!(outcome, TxOutcome::Done));
+    assert_eq!(chip.transmitted(), vec![b"async".to_vec()]);
+}
+
+#[test]
+fn random_u32_restores_standby_and_irqs() {
+    let chip = MockChip::new();
+    let mut sx = SX126x::new(chip.spi(), chip.pins(), MockDelay);
+    sx.init(Config::default()).unwrap();
+
+    let first = sx.random_u32().unwrap();
+    let second = block_on(sx.random_u32_async()).unwrap();
+    assert_ne!(first, second);
+    assert_eq!(chip.mode(), Mode::StbyRc);
+
+    // TxDone is mapped to DIO1 again, so the transmission completes
+    let outcome = sx
+        .write_bytes(b"hi", RxTxTimeout::from_ms(100), packet_params(2))
+        .unwrap();
+    assert!(matches!(outcome, TxOutcome::Done));
+}
+
+#[test]
+fn async_spi_driver_random_u32() {
+    let chip = MockChip::new();
+    let mut sx = SX126xAsync::new(chip.spi(), chip.pins());
+    block_on(sx.init(Config::default())).unwrap();
+
+    let first = block_on(sx.random_u32()).unwrap();
+    let second = block_on(sx.random_u32()).unwrap();
+    assert_ne!(first, second);
+    assert_eq!(chip.mode(), Mode::StbyRc);
+}
+
+#[test]
+fn init_and_write_bytes_with_status_checking() {
+    let chip = MockChip::new();
+    let mut sx = SX126x::new(chip.spi(), chip.pins(), MockDelay);
+    sx.set_check_status(true);
+
+    sx.init(Config::default()).unwrap();
+    let outcome = sx
+        .write_bytes(b"hi", RxTxTimeout::from_ms(100), packet_params(2))
+        .unwrap();
+    assert!(matches!(outcome, TxOutcome::Done));
+}
+
+#[test]
+fn read_register_returns_register_value_not_status() {
+    let chip = MockChip::new();
+    let mut sx = SX126x::new(chip.spi(), chip.pins(), MockDelay);
+    block_on(sx.init_async(Config::default())).unwrap();
+
+    // The status byte clocked out after the address is never zero,
+    // so reading it into the result would show up here
+    sx.write_register(Register::RxGain, &[0x96]).unwrap();
+    let mut result = [0; 3];
+    sx.read_register(Register::LoRaSyncWordMsb, &mut result)
+        .unwrap();
+    assert_eq!(result[..2], [0x00, 0x12]);
+    assert_eq!(sx.read_reg(Register::RxGain).unwrap(), 0x96);
+}
+
+#[test]
+fn blocking_write_and_read_bytes_time_out_if_dio1_stays_low() {
+    let chip = MockChip::new();
+    let mut sx = SX126x::new(chip.spi(), chip.pins(), MockDelay);
+    sx.init(Config::default()).unwrap();
+
+    // Route no IRQ to DIO1, so the end of the TX or RX is never signalled
+    sx.set_dio_irq_params(
+        IrqMask::all(),
+        IrqMask::none(),
+        IrqMask::none(),
+        IrqMask::none(),
+    )
+    .unwrap();
+
+    // Without a TX timeout the deadline follows from the time on air
+    let err = sx
+        .write_bytes(b"hi", RxTxTimeout::from(0), packet_params(2))
+        .unwrap_err();
+    assert!(matches!(err, SxError::Timeout));
+
+    sx.set_standby(StandbyConfig::StbyRc).unwrap();
+    sx.wait_on_busy().unwrap();
+    let mut buf = [0; 16];
+    let err = sx
+        .read_bytes(&mut buf, RxTxTimeout::from_ms(10))
+        .unwrap_err();
+    assert!(matches!(err, SxError::Timeout));
+}
+
+#[test]
+fn async_spi_driver_restores_gfsk_config_on_wake() {
+    let chip = MockChip::new();
+    let mut sx = SX126xAsync::new(chip.spi(), chip.pins());
+    let conf = Config {
+        packet_type: PacketType::GFSK,
+        mod_params: GfskModParams::default().into(),
+        packet_params: Some(
+            GfskPacketParams::default()
+                .set_addr_comp(GfskAddrComp::NodeAndBroadcast)
+                .into(),
+        ),
+        gfsk_address: GfskAddress {
+            node: 0x12,
+            broadcast: 0xFE,
+        },
+        ..Config::default()
+    };
+    block_on(sx.init(conf)).unwrap();
+
+    block_on(sx.sleep(SleepConfig::default())).unwrap();
+    // The command would only wake the modem, which then drops it
+    let err = block_on(sx.set_gfsk_node_address(0x34)).unwrap_err();
+    assert!(matches!(err, SxError::Asleep));
+    assert_eq!(chip.mode(), Mode::Sleep);
+    block_on(sx.wake()).unwrap();
+
+    assert_eq!(chip.mode(), Mode::StbyRc);
+    assert_eq!(chip.packet_type(), Some(PacketType::GFSK));
+    assert_eq!(chip.register(Register::NodeAddress), 0x12);
+    assert_eq!(chip.register(Register::BroadcastAddress), 0xFE);
+}
+
+#[test]
+fn runtime_settings_are_restored_on_wake() {
+    let chip = MockChip::new();
+    let mut sx = SX126x::new(chip.spi(), chip.pins(), MockDelay);
+    let conf = Config {
+        packet_type: PacketType::GFSK,
+        mod_params: GfskModParams::default().into(),
+        packet_params: Some(
+            GfskPacketParams::default()
+                .set_addr_comp(GfskAddrComp::NodeAndBroadcast)
+                .into(),
+        ),
+        ..Config::default()
+    };
+    sx.init(conf).unwrap();
+
+    sx.set_gfsk_sync_word(&[0xC1, 0x94, 0xC1]).unwrap();
+    sx.set_gfsk_crc(GfskCrc::CCITT).unwrap();
+    sx.set_whitening_seed(0x01A5).unwrap();
+    sx.set_gfsk_node_address(0x12).unwrap();
+    sx.set_gfsk_broadcast_address(0xFE).unwrap();
+    sx.set_dio_irq_params(
+        IrqMask::all(),
+        IrqMask::none().combine(IrqMaskBit::TxDone),
+        IrqMask::none(),
+        IrqMask::none(),
+    )
+    .unwrap();
+    // The random number flow temporarily disables all IRQs
+    sx.random_u32().unwrap();
+
+    sx.sleep(SleepConfig::default()).unwrap();
+    sx.wake().unwrap();
+
+    assert_eq!(chip.register(Register::SyncWord0), 0xC1);
+    assert_eq!(chip.register(Register::SyncWord1), 0x94);
+    assert_eq!(chip.register(Register::SyncWord2), 0xC1);
+    assert_eq!(chip.register(Register::CrcMsbPolynomialValue), 0x10);
+    assert_eq!(chip.register(Register::CrcLsbPolynomialValue), 0x21);
+    assert_eq!(chip.register(Register::CrcMsbInitialValue), 0x1D);
+    assert_eq!(chip.register(Register::CrcLsbInitialValue), 0x0F);
+    assert_eq!(
+        chip.register(Register::WhiteningInitialValueMsb) & 0x01,
+        0x01
+    );
+    assert_eq!(chip.register(Register::WhiteningInitialValueLsb), 0xA5);
+    assert_eq!(chip.register(Register::NodeAddress), 0x12);
+    assert_eq!(chip.register(Register::BroadcastAddress), 0xFE);
+
+    // Only TxDone is routed to DIO1
+    chip.raise_irq(IrqMaskBit::RxDone);
+    assert!(!chip.dio1());
+    chip.raise_irq(IrqMaskBit::TxDone);
+    assert!(chip.dio1());
+}
+
+#[test]
+fn dropped_test_tx_ends_in_standby() {
+    let chip = MockChip::new();
+    let mut sx = SX126x::new(chip.spi(), chip.pins(), MockDelay);
+    sx.init(Config::default()).unwrap();
+
+    let test_tx = sx.set_tx_continuous_wave().unwrap();
+    assert_eq!(chip.mode(), Mode::Tx);
+    drop(test_tx);
+    assert_eq!(chip.mode(), Mode::StbyRc);
+
+    // A stopped test is left alone on drop
+    sx.set_standby(StandbyConfig::StbyXOSC).unwrap();
+    let test_tx = sx.set_tx_infinite_preamble().unwrap();
+    test_tx.stop().unwrap();
+    assert_eq!(chip.mode(), Mode::StbyXosc);
+
+    let chip = MockChip::new();
+    let mut sx = SX126xAsync::new(chip.spi(), chip.pins());
+    block_on(sx.init(Config::default())).unwrap();
+
+    // Drop cannot await, the next command ends the test first
+    let test_tx = block_on(sx.set_tx_infinite_preamble()).unwrap();
+    assert_eq!(chip.mode(), Mode::Tx);
+    drop(test_tx);
+    assert_eq!(chip.mode(), Mode::Tx);
+    let status = block_on(sx.get_status()).unwrap();
+    assert_eq!(status.chip_mode(), ChipMode::StbyRC);
+    assert_eq!(chip.mode(), Mode::StbyRc);
+
+    // Only once
+    block_on(sx.set_standby(StandbyConfig::StbyXOSC)).unwrap();
+    block_on(sx.get_status()).unwrap();
+    assert_eq!(chip.mode(), Mode::StbyXosc);
+
+    // A reset ends the test as well
+    let test_tx = block_on(sx.set_tx_continuous_wave()).unwrap();
+    drop(test_tx);
+    block_on(sx.init(Config::default())).unwrap();
+    let status = block_on(sx.get_status()).unwrap();
+    assert_eq!(status.chip_mode(), ChipMode::StbyRC);
+}
+
+#[test]
+fn packet_rejected_by_address_filter_is_reported() {
+    let chip = MockChip::new();
+    let mut sx = SX126x::new(chip.spi(), chip.pins(), MockDelay);
+    let conf = Config {
+        packet_type: PacketType::GFSK,
+        mod_params: GfskModParams::default().into(),
+        packet_params: Some(
+            GfskPacketParams::default()
+                .set_addr_comp(GfskAddrComp::NodeAndBroadcast)
+                .into(),
+        ),
+        gfsk_address: GfskAddress {
+            node: 0x12,
+            broadcast: 0xFE,
+        },
+        // The dropped packet raises no IRQ, the reception ends at the RX timeout
+        dio1_irq_mask: IrqMask::none()
+            .combine(IrqMaskBit::RxDone)
+            .combine(IrqMaskBit::Timeout),
+        ..Config::default()
+    };
+    sx.init(conf).unwrap();
+    chip.queue_rx(&[0x34, 0xAA]);
+    chip.queue_rx(&[0xFE, 0xBB]);
+
+    let mut buf = [0; 16];
+    let timeout = RxTxTimeout::from_ms(100);
+    let err = sx.read_bytes(&mut buf, timeout).unwrap_err();
+    assert!(matches!(err, SxError::Rx(RxError::AddressFiltered)));
+
+    let packet = sx.read_bytes(&mut buf, timeout).unwrap();
+    assert_eq!(&buf[..packet.len], &[0xFE, 0xBB]);
+}
+
+/// Send a command straight to the mock, after waiting for BUSY like the driver does
+fn send(chip: &MockChip, mosi: &[u8]) -> Result<Vec<u8>, MockError> {
+    let (_, mut busy, _, _) = chip.pins();
+    while busy.is_high().unwrap() {}
+    let mut buf = mosi.to_vec();
+    chip.spi().transfer_in_place(&mut buf)?;
+    Ok(buf)
+}
+
+#[test]
+fn every_command_drives_busy() {
+    let chip = MockChip::new();
+    let (_, mut busy, _, _) = chip.pins();
+
+    // GetStatus, WriteRegister and SetBufferBaseAddress take no time on
+    // the modem, but BUSY still goes high after each of them
+    for mosi in [
+        &[0xC0, 0x00][..],
+        &[0x0D, 0x08, 0xAC, 0x96],
+        &[0x8F, 0x00, 0x80],
+    ] {
+        send(&chip, mosi).unwrap();
+        assert!(chip.busy());
+        let err = chip.spi().transfer_in_place(&mut [0xC0, 0x00]).unwrap_err();
+        assert_eq!(err, MockError::Busy { opcode: 0xC0 });
+
+        assert!(busy.is_high().unwrap());
+        assert!(busy.is_low().unwrap());
+    }
+}
+
+#[test]
+fn busy_is_high_while_asleep_or_in_reset() {
+    let chip = MockChip::new();
+    let (mut nrst, mut busy, _, _) = chip.pins();
+
+    nrst.set_low().unwrap();
+    assert!(busy.is_high().unwrap());
+    assert!(busy.is_high().unwrap());
+    assert_eq!(block_on(busy.wait_for_low()), Err(MockError::Deadlock));
+    nrst.set_high().unwrap();
+    assert!(busy.is_high().unwrap());
+    assert!(busy.is_low().unwrap());
+
+    send(&chip, &[0x84, 0x00]).unwrap();
+    assert_eq!(chip.mode(), Mode::Sleep);
+    assert!(busy.is_high().unwrap());
+    assert!(busy.is_high().unwrap());
+    assert_eq!(block_on(busy.wait_for_low()), Err(MockError::Deadlock));
+
+    // Pulling NSS low wakes the modem, even without clocking out any bytes
+    chip.spi()
+        .transaction(&mut [Operation::DelayNs(1000)])
+        .unwrap();
+    assert_eq!(chip.mode(), Mode::StbyRc);
+    assert!(busy.is_high().unwrap());
+    assert!(busy.is_low().unwrap());
+}
+
+#[test]
+fn commands_are_rejected_in_the_wrong_mode() {
+    /// Commands that bring a fresh modem into the mode under test
+    type Setup = &'static [&'static [u8]];
+    let stby_xosc: Setup = &[&[0x80, 0x01]];
+    let fs: Setup = &[&[0xC1, 0x00]];
+    let rx: Setup = &[&[0x8A, 0x01], &[0x82, 0xFF, 0xFF, 0xFF]];
+    let tx: Setup = &[&[0x8A, 0x01], &[0xD1, 0x00]];
+    let cases: [(&str, Setup, &[u8], Mode); 12] = [
+        ("SetSleep", fs, &[0x84, 0x00], Mode::Fs),
+        ("SetFs", rx, &[0xC1, 0x00], Mode::Rx),
+        ("SetTx", rx, &[0x83, 0x00, 0x00, 0x00], Mode::Rx),
+        ("SetRx", tx, &[0x82, 0x00, 0x00, 0x00], Mode::Tx),
+        (
+            "SetRxDutyCycle",
+            fs,
+            &[0x94, 0x00, 0x00, 0x01, 0x00, 0x00, 0x01],
+            Mode::Fs,
+        ),
+        ("SetCad", rx, &[0xC5, 0x00], Mode::Rx),
+        ("SetTxContinuousWave", rx, &[0xD1, 0x00], Mode::Rx),
+        ("SetTxInfinitePreamble", tx, &[0xD2, 0x00], Mode::Tx),
+        ("Calibrate", stby_xosc, &[0x89, 0x7F], Mode::StbyXosc),
+        (
+            "CalibrateImage",
+            stby_xosc,
+            &[0x98, 0xD7, 0xDB],
+            Mode::StbyXosc,
+        ),
+        (
+            "SetDIO3AsTCXOCtrl",
+            stby_xosc,
+            &[0x97, 0x02, 0x00, 0x00, 0x40],
+            Mode::StbyXosc,
+        ),
+        ("SetPacketType", fs, &[0x8A, 0x00], Mode::Fs),
+    ];
+
+    for (name, setup, mosi, mode) in cases {
+        let chip = MockChip::new();
+        for setup_mosi in setup {
+            send(&chip, setup_mosi).unwrap();
+        }
+        assert_eq!(chip.mode(), mode, "{name}");
+        assert_eq!(
+            send(&chip, mosi),
+            Err(MockError::InvalidMode {
+                opcode: mosi[0],
+                mode
+            }),
+            "{name}"
+        );
+    }
+}
+
+#[test]
+fn init_reports_device_errors_after_calibration() {
+    let chip = MockChip::new();
+    // PLL calibration failed
+    chip.set_calib_errors(1 << 2);
+    let mut sx = SX126x::new(chip.spi(), chip.pins(), MockDelay);
+    let err = block_on(sx.init_async(Config::default())).unwrap_err();
+    assert!(matches!(err, SxError::Device(errors) if errors.pll_calib_err()));
+
+    let chip = MockChip::new();
+    chip.set_calib_errors(1 << 4);
+    let mut sx = SX126xAsync::new(chip.spi(), chip.pins());
+    let err = block_on(sx.init(Config::default())).unwrap_err();
+    assert!(matches!(err, SxError::Device(errors) if errors.img_calib_err()));
+}
@@ -0,0 +1,103 @@
+use sx126x::op::*;
+
+fn lora(
+    spread_factor: LoRaSpreadFactor,
+    bandwidth: LoRaBandWidth,
+    coding_rate: LoraCodingRate,
+) -> LoraModParams {
+    LoraModParams::default()
+        .set_spread_factor(spread_factor)
+        .set_bandwidth(bandwidth)
+        .set_coding_rate(coding_rate)
+}
+
+fn explicit_with_crc() -> LoRaPacketParams {
+    LoRaPacketParams::default()
+        .set_header_type(LoRaHeaderType::VarLen)
+        .set_crc_type(LoRaCrcType::CrcOn)
+}
+
+fn implicit_without_crc() -> LoRaPacketParams {
+    LoRaPacketParams::default()
+        .set_header_type(LoRaHeaderType::FixedLen)
+        .set_crc_type(LoRaCrcType::CrcOff)
+}
+
+#[test]
+fn sf7_explicit_header() {
+    // 8 + 4.25 + 8 + ceil(68 / 28) * 5 = 40.25 symbols of 1.024 ms
+    let mod_params = lora(
+        LoRaSpreadFactor::SF7,
+        LoRaBandWidth::BW125,
+        LoraCodingRate::CR4_5,
+    );
+    let toa = time_on_air(&mod_params.into(), &explicit_with_crc(), 10);
+    assert_eq!(toa, Some(41_216));
+}
+
+#[test]
+fn sf12_low_data_rate_optimization() {
+    // 8 + 4.25 + 8 + ceil(76 / 40) * 5 = 30.25 symbols of 32.768 ms
+    let mod_params = lora(
+        LoRaSpreadFactor::SF12,
+        LoRaBandWidth::BW125,
+        LoraCodingRate::CR4_5,
+    )
+    .set_low_dr_opt(true);
+    let toa = time_on_air(&mod_params.into(), &explicit_with_crc(), 10);
+    assert_eq!(toa, Some(991_232));
+}
+
+#[test]
+fn sf5_implicit_header() {
+    // 8 + 6.25 + 8 + 0 = 22.25 symbols of 64 μs, the payload fits in the fixed symbols
+    let mod_params = lora(
+        LoRaSpreadFactor::SF5,
+        LoRaBandWidth::BW500,
+        LoraCodingRate::CR4_5,
+    );
+    let toa = time_on_air(&mod_params.into(), &implicit_without_crc(), 1);
+    assert_eq!(toa, Some(1_424));
+}
+
+#[test]
+fn sf6_explicit_header() {
+    // 8 + 6.25 + 8 + ceil(172 / 24) * 8 = 86.25 symbols of 512 μs
+    let mod_params = lora(
+        LoRaSpreadFactor::SF6,
+        LoRaBandWidth::BW125,
+        LoraCodingRate::CR4_8,
+    );
+    let toa = time_on_air(&mod_params.into(), &explicit_with_crc(), 20);
+    assert_eq!(toa, Some(44_160));
+}
+
+#[test]
+fn sf9_implicit_header_long_preamble() {
+    // 12 + 4.25 + 8 + ceil(380 / 36) * 7 = 101.25 symbols of 2.048 ms
+    let mod_params = lora(
+        LoRaSpreadFactor::SF9,
+        LoRaBandWidth::BW250,
+        LoraCodingRate::CR4_7,
+    );
+    let packet_params = implicit_without_crc().set_preamble_len(12);
+    let toa = time_on_air(&mod_params.into(), &packet_params, 51);
+    assert_eq!(toa, Some(207_360));
+}
+
+#[test]
+fn empty_payload() {
+    let mod_params = lora(
+        LoRaSpreadFactor::SF7,
+        LoRaBandWidth::BW125,
+        LoraCodingRate::CR4_5,
+    );
+    let toa = time_on_air(&mod_params.into(), &implicit_without_crc(), 0);
+    assert_eq!(toa, Some(20_736));
+}
+
+#[test]
+fn gfsk_has_no_lora_time_on_air() {
+    let mod_params = GfskModParams::default().into();
+    assert_eq!(time_on_air(&mod_params, &explicit_with_crc(), 10), None);
+}